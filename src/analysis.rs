@@ -0,0 +1,126 @@
+//! Spectral analysis of the motion of a single pole
+
+use std::{collections::VecDeque, f32::consts::TAU};
+
+use bevy::prelude::*;
+
+use crate::{chain::TorsionChain, complex::Complex};
+
+/// Samples kept for the transform, a power of 2
+const CAPACITY: usize = 1024;
+
+/// Recent angles of the observed pole, sampled at regular intervals
+pub struct Spectrum {
+    /// Index of the observed pole
+    pub pole: usize,
+    /// Time between samples
+    pub interval: f32,
+    samples: VecDeque<f32>,
+    /// Time of the next sample
+    next_sample: f64,
+}
+
+impl Default for Spectrum {
+    fn default() -> Self {
+        Self {
+            pole: 0,
+            interval: 0.05,
+            samples: VecDeque::with_capacity(CAPACITY),
+            next_sample: 0.0,
+        }
+    }
+}
+
+impl Spectrum {
+    /// Discards collected samples, e.g. after choosing a different pole
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Span of time covered by the collected samples
+    pub fn duration(&self) -> f32 {
+        self.interval * self.samples.len() as f32
+    }
+
+    /// Magnitudes of the frequency components, paired with their frequencies
+    /// The mean is removed and a Hann window applied, samples are zero-padded to a power of 2
+    pub fn magnitudes(&self) -> Vec<(f32, f32)> {
+        let n = self.samples.len();
+        if n < 2 {
+            return Vec::new();
+        }
+        let mean = self.samples.iter().sum::<f32>() / n as f32;
+        let size = n.next_power_of_two();
+        let mut values = vec![Complex::default(); size];
+        for (i, (value, sample)) in values.iter_mut().zip(&self.samples).enumerate() {
+            let window = 0.5 - 0.5 * (TAU * i as f32 / (n - 1) as f32).cos();
+            *value = ((sample - mean) * window).into();
+        }
+        fft(&mut values);
+        // The window halves the amplitude
+        let scale = 4.0 / n as f32;
+        let resolution = 1.0 / (size as f32 * self.interval);
+        values[..=size / 2]
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i as f32 * resolution, value.abs() * scale))
+            .collect()
+    }
+}
+
+/// In-place radix-2 Cooley-Tukey transform, the length must be a power of 2
+fn fft(values: &mut [Complex]) {
+    let n = values.len();
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+    let mut size = 2;
+    while size <= n {
+        let step = Complex::from_polar(1.0, -TAU / size as f32);
+        for chunk in values.chunks_mut(size) {
+            let mut twiddle = Complex::from(1.0);
+            for i in 0..size / 2 {
+                let odd = chunk[i + size / 2] * twiddle;
+                chunk[i + size / 2] = chunk[i] - odd;
+                chunk[i] = chunk[i] + odd;
+                twiddle = twiddle * step;
+            }
+        }
+        size *= 2;
+    }
+}
+
+/// Samples the observed pole after each physics step
+fn sample(mut spectrum: ResMut<Spectrum>, chain: Res<TorsionChain>) {
+    // Starts over when time jumped backwards
+    if spectrum.next_sample - chain.time > spectrum.interval as f64 {
+        spectrum.clear();
+        spectrum.next_sample = chain.time;
+    }
+    if chain.time < spectrum.next_sample - 1e-6 {
+        return;
+    }
+    let angle = match chain.angles.get(spectrum.pole) {
+        Some(angle) => *angle,
+        None => return,
+    };
+    if spectrum.samples.len() == CAPACITY {
+        spectrum.samples.pop_front();
+    }
+    spectrum.samples.push_back(angle);
+    let interval = spectrum.interval as f64;
+    spectrum.next_sample = (spectrum.next_sample + interval).max(chain.time);
+}
+
+pub struct AnalysisPlugin;
+
+impl Plugin for AnalysisPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Spectrum>()
+            .add_system(sample.with_run_criteria("physics-tick").after("step-chain"));
+    }
+}
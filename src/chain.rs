@@ -0,0 +1,942 @@
+//! Headless simulation core
+//! Plain Rust state of the pole chain, independent from Bevy ECS
+
+use std::{
+    f32::consts::TAU,
+    ops::{Div, Mul, Sub, SubAssign},
+};
+
+use crate::{
+    complex::Complex,
+    drive::Drive,
+    settings::{Boundary, DampingModel, HardSettings, Integrator, Model, SoftSettings},
+};
+
+/// State of the whole chain
+/// Poles are ordered from the bottom-most (index 0) to the top-most
+#[derive(Clone, Default)]
+pub struct TorsionChain {
+    /// Angle of every pole, unwrapped (keeps the winding)
+    pub angles: Vec<f32>,
+    /// Angular velocity of every pole
+    pub velocities: Vec<f32>,
+    /// Moment of inertia per unit length of every pole
+    pub inertias: Vec<f32>,
+    /// Torsional rigidity of the link below every pole
+    /// The top anchor link uses the rigidity of the top-most pole
+    pub rigidities: Vec<f32>,
+    /// Simulation time
+    pub time: f64,
+    /// Parameters of the simulation
+    pub settings: SoftSettings,
+    /// Distance between poles
+    /// Continuous quantities from settings (per unit length) are scaled by it for each pole
+    pub distance: f32,
+    /// Pole held by the user
+    pub grab: Option<Grab>,
+    /// Natural angular frequency of the grab spring, limited by the last step length
+    grab_frequency: f32,
+}
+
+/// Pole pulled towards a target angle
+#[derive(Clone, Copy)]
+pub struct Grab {
+    pub index: usize,
+    pub target: f32,
+}
+
+/// Energy of the chain and power flowing in and out of it
+#[derive(Clone, Copy, Default)]
+pub struct Energy {
+    pub kinetic: f32,
+    /// Energy stored in the twist of the links, supporting strings and gravity
+    pub potential: f32,
+    /// Power delivered by agitation at the ends and by grabbing
+    pub injected: f32,
+    /// Power lost to damping
+    pub dissipated: f32,
+}
+
+impl Energy {
+    pub fn total(&self) -> f32 {
+        self.kinetic + self.potential
+    }
+}
+
+/// Natural angular frequency of the spring pulling grabbed poles, stiff enough to dominate the links
+const GRAB_FREQUENCY: f32 = 100.0;
+
+/// Product of the grab frequency and the step length, above which the spring is softened
+/// Keeps the explicit treatment of the spring stable for long steps
+const GRAB_STEP_LIMIT: f32 = 0.5;
+
+impl TorsionChain {
+    /// Creates a chain of `amount` poles at rest
+    pub fn new(amount: usize, soft_settings: &SoftSettings, hard_settings: &HardSettings) -> Self {
+        Self {
+            angles: vec![0.0; amount],
+            velocities: vec![0.0; amount],
+            inertias: vec![soft_settings.moment_of_inertia; amount],
+            rigidities: vec![soft_settings.stiffness; amount],
+            time: 0.0,
+            settings: soft_settings.clone(),
+            distance: hard_settings.distance,
+            grab: None,
+            grab_frequency: GRAB_FREQUENCY,
+        }
+    }
+
+    /// Advances the simulation by `dt` using the selected integrator
+    pub fn step(&mut self, dt: f32) {
+        self.grab_frequency = GRAB_FREQUENCY.min(GRAB_STEP_LIMIT / dt);
+        match self.settings.integrator {
+            Integrator::SymplecticEuler => self.symplectic_euler(dt),
+            Integrator::VelocityVerlet => self.velocity_verlet(dt),
+            Integrator::RungeKutta4 => self.runge_kutta_4(dt),
+            Integrator::CrankNicolson => self.crank_nicolson(dt),
+        }
+        self.time += dt as f64;
+    }
+
+    /// Calculates the energy of the current state
+    pub fn energy(&self) -> Energy {
+        let n = self.angles.len();
+        let kinetic = self
+            .pole_inertias()
+            .iter()
+            .zip(&self.velocities)
+            .map(|(i, v)| i * v * v / 2.0)
+            .sum();
+
+        // Links pull towards anchors at the drive angle, or at 0 if not driven
+        let links = self.links();
+        let anchor = |boundary: Boundary, drive: &Drive| match boundary {
+            Boundary::Driven => drive.value(self.time),
+            _ => 0.0,
+        };
+        let bottom = anchor(self.settings.bottom_boundary, &self.settings.bottom_drive);
+        let top = anchor(self.settings.top_boundary, &self.settings.top_drive);
+        let twist = |i: usize| {
+            let below = i.checked_sub(1).map_or(bottom, |below| self.angles[below]);
+            let above = self.angles.get(i).copied().unwrap_or(top);
+            above - below
+        };
+        let restoring = self.restoring_stiffness();
+        let gravity = self.gravity_strength();
+        let potential = (0..=n)
+            .map(|i| links[i] * twist(i).powi(2) / 2.0)
+            .chain(
+                self.angles
+                    .iter()
+                    .map(|&angle| restoring * angle.powi(2) / 2.0 + gravity * (1.0 - angle.cos())),
+            )
+            .sum();
+
+        // Torque driven ends push the end pole, angle driven ends move the anchor
+        let power = |boundary: Boundary, drive: &Drive, link: f32, angle: f32, velocity: f32| {
+            match boundary {
+                Boundary::Driven => {
+                    let h = 1e-4;
+                    let anchor_velocity = (drive.value(self.time + h) - drive.value(self.time - h))
+                        / (2.0 * h) as f32;
+                    link * (drive.value(self.time) - angle) * anchor_velocity
+                }
+                Boundary::Free | Boundary::Fixed | Boundary::Absorbing => {
+                    drive.value(self.time) * velocity
+                }
+            }
+        };
+        let mut injected = 0.0;
+        if n > 0 {
+            let settings = &self.settings;
+            injected += power(
+                settings.bottom_boundary,
+                &settings.bottom_drive,
+                links[0],
+                self.angles[0],
+                self.velocities[0],
+            );
+            injected += power(
+                settings.top_boundary,
+                &settings.top_drive,
+                links[n],
+                self.angles[n - 1],
+                self.velocities[n - 1],
+            );
+        }
+        injected += self
+            .grab_torques(&self.angles, &self.velocities)
+            .iter()
+            .zip(&self.velocities)
+            .map(|(t, v)| t * v)
+            .sum::<f32>();
+
+        let dissipated = -self
+            .damping_torques(&self.velocities)
+            .iter()
+            .zip(&self.velocities)
+            .map(|(t, v)| t * v)
+            .sum::<f32>();
+
+        Energy {
+            kinetic,
+            potential,
+            injected,
+            dissipated,
+        }
+    }
+
+    /// Angles at `time` in the steady state of the linearised chain driven by sine waves
+    /// Nonlinear friction is ignored and the sine-Gordon gravity is linearised around rest
+    /// `None` if an end is driven by a different waveform, or the response is unbounded
+    pub fn steady_state(&self, time: f64) -> Option<Vec<f32>> {
+        let n = self.angles.len();
+        let mut angles = vec![0.0; n];
+        if n == 0 {
+            return Some(angles);
+        }
+        let links = self.links();
+        let settings = &self.settings;
+        let ends = [
+            (0, settings.bottom_boundary, settings.bottom_drive, links[0]),
+            (n - 1, settings.top_boundary, settings.top_drive, links[n]),
+        ];
+        for (index, boundary, drive, link) in ends {
+            if drive.amplitude() == 0.0 {
+                continue;
+            }
+            let (frequency, phase, amplitude) = match drive {
+                Drive::Sine {
+                    frequency,
+                    phase,
+                    amplitude,
+                } => (frequency, phase, amplitude),
+                _ => return None,
+            };
+            let torque = match boundary {
+                Boundary::Driven => amplitude * link,
+                Boundary::Free | Boundary::Fixed | Boundary::Absorbing => amplitude,
+            };
+            let omega = TAU * frequency;
+            let response =
+                self.harmonic_response(index, Complex::from_polar(torque, phase), omega)?;
+            // The drive is the imaginary part of `e^(i * omega * t)`
+            let rotation =
+                Complex::from_polar(1.0, (time * omega as f64).rem_euclid(TAU as f64) as f32);
+            for (angle, amplitude) in angles.iter_mut().zip(response) {
+                *angle += (amplitude * rotation).im;
+            }
+        }
+        Some(angles)
+    }
+
+    /// Complex amplitudes of all poles, when pole `index` is driven by complex amplitude `torque`
+    /// at angular frequency `omega`
+    ///
+    /// Solves `(K - omega^2 I - i omega C) x = f`, where `K` is the linearised coupling matrix
+    /// and `C` the damping matrix
+    fn harmonic_response(&self, index: usize, torque: Complex, omega: f32) -> Option<Vec<Complex>> {
+        let n = self.angles.len();
+        let inertias = self.pole_inertias();
+        let dampings = self.pole_dampings();
+        let internal = self.internal_links();
+        let links = self.links();
+        let restoring = self.restoring_stiffness() + self.gravity_strength();
+
+        let off_diagonal = |i: usize| Complex::new(-links[i], -omega * internal[i]);
+        let lower = (0..n).map(off_diagonal).collect::<Vec<_>>();
+        let upper = (0..n).map(|i| off_diagonal(i + 1)).collect::<Vec<_>>();
+        let diagonal = (0..n)
+            .map(|i| {
+                Complex::new(
+                    links[i] + links[i + 1] + restoring - omega * omega * inertias[i],
+                    omega * (internal[i] + internal[i + 1] - dampings[i]),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut rhs = vec![Complex::default(); n];
+        rhs[index] = torque;
+
+        let response = solve_tridiagonal(&lower, &diagonal, &upper, rhs);
+        response.iter().all(Complex::is_finite).then_some(response)
+    }
+
+    /// Velocities are updated first, then the angles use the new velocities
+    fn symplectic_euler(&mut self, dt: f32) {
+        let accelerations = self.accelerations(&self.angles, &self.velocities, self.time);
+        add_scaled(&mut self.velocities, &accelerations, dt);
+        add_scaled(&mut self.angles, &self.velocities, dt);
+    }
+
+    /// Half-step in velocity, full step in angle, another half-step in velocity
+    /// Velocity dependent torques are evaluated with the half-step velocity
+    fn velocity_verlet(&mut self, dt: f32) {
+        let accelerations = self.accelerations(&self.angles, &self.velocities, self.time);
+        add_scaled(&mut self.velocities, &accelerations, dt / 2.0);
+        add_scaled(&mut self.angles, &self.velocities, dt);
+        let time = self.time + dt as f64;
+        let accelerations = self.accelerations(&self.angles, &self.velocities, time);
+        add_scaled(&mut self.velocities, &accelerations, dt / 2.0);
+    }
+
+    /// Classic 4th order Runge-Kutta
+    fn runge_kutta_4(&mut self, dt: f32) {
+        let derivative = |angles: &[f32], velocities: &[f32], time: f64| {
+            (
+                velocities.to_vec(),
+                self.accelerations(angles, velocities, time),
+            )
+        };
+        let half = self.time + dt as f64 / 2.0;
+        let end = self.time + dt as f64;
+
+        let k1 = derivative(&self.angles, &self.velocities, self.time);
+        let k2 = derivative(
+            &scaled_sum(&self.angles, &k1.0, dt / 2.0),
+            &scaled_sum(&self.velocities, &k1.1, dt / 2.0),
+            half,
+        );
+        let k3 = derivative(
+            &scaled_sum(&self.angles, &k2.0, dt / 2.0),
+            &scaled_sum(&self.velocities, &k2.1, dt / 2.0),
+            half,
+        );
+        let k4 = derivative(
+            &scaled_sum(&self.angles, &k3.0, dt),
+            &scaled_sum(&self.velocities, &k3.1, dt),
+            end,
+        );
+
+        let combine = |x: &mut [f32], k: [&[f32]; 4]| {
+            for (i, x) in x.iter_mut().enumerate() {
+                *x += dt / 6.0 * (k[0][i] + 2.0 * k[1][i] + 2.0 * k[2][i] + k[3][i]);
+            }
+        };
+        combine(&mut self.angles, [&k1.0, &k2.0, &k3.0, &k4.0]);
+        combine(&mut self.velocities, [&k1.1, &k2.1, &k3.1, &k4.1]);
+    }
+
+    /// Crank-Nicolson (trapezoidal rule)
+    /// Coupling between poles, restoring torque, viscous and internal damping are treated implicitly,
+    /// which keeps stiff chains stable
+    /// Agitation, nonlinear gravity, friction and grabbing are treated explicitly
+    ///
+    /// Substituting the angle update into the velocity update leaves a tridiagonal system for the new velocities:
+    /// `(I - dt/2 C + dt^2/4 K) w1 = (I + dt/2 C - dt^2/4 K) w0 - dt K a0 + dt f`
+    /// where `K` is the coupling matrix (including the restoring torque) and `C` the damping matrix
+    fn crank_nicolson(&mut self, dt: f32) {
+        let n = self.angles.len();
+        let inertias = self.pole_inertias();
+        let dampings = self.pole_dampings();
+        let internal = self.internal_links();
+        let links = self.links();
+        let restoring = self.restoring_stiffness();
+        let coupling = dt * dt / 4.0;
+
+        let lower = (0..n)
+            .map(|i| -coupling * links[i] - dt / 2.0 * internal[i])
+            .collect::<Vec<_>>();
+        let upper = (0..n)
+            .map(|i| -coupling * links[i + 1] - dt / 2.0 * internal[i + 1])
+            .collect::<Vec<_>>();
+        let diagonal = (0..n)
+            .map(|i| {
+                inertias[i] - dt / 2.0 * dampings[i]
+                    + dt / 2.0 * (internal[i] + internal[i + 1])
+                    + coupling * (links[i] + links[i + 1] + restoring)
+            })
+            .collect::<Vec<_>>();
+
+        let elastic_angles = self.elastic_torques(&self.angles);
+        let elastic_velocities = self.elastic_torques(&self.velocities);
+        let internal_velocities = neighbour_torques(&self.velocities, &internal);
+        let friction = self.friction_torques(&self.velocities);
+        let agitation = self.agitation_torques(self.time + dt as f64 / 2.0);
+        let gravity = self.gravity_torques(&scaled_sum(&self.angles, &self.velocities, dt / 2.0));
+        let grab = self.grab_torques(&self.angles, &self.velocities);
+        let rhs = (0..n)
+            .map(|i| {
+                (inertias[i] + dt / 2.0 * dampings[i]) * self.velocities[i]
+                    + dt / 2.0 * internal_velocities[i]
+                    + coupling * elastic_velocities[i]
+                    + dt * elastic_angles[i]
+                    + dt * (agitation[i] + gravity[i] + friction[i] + grab[i])
+            })
+            .collect::<Vec<_>>();
+
+        let velocities = solve_tridiagonal(&lower, &diagonal, &upper, rhs);
+        for ((angle, old), new) in self
+            .angles
+            .iter_mut()
+            .zip(&self.velocities)
+            .zip(&velocities)
+        {
+            *angle += dt / 2.0 * (old + new);
+        }
+        self.velocities = velocities;
+    }
+
+    /// Moment of inertia of every pole
+    fn pole_inertias(&self) -> Vec<f32> {
+        self.inertias.iter().map(|i| i * self.distance).collect()
+    }
+
+    /// Viscous damping coefficient of every pole
+    /// Poles in sponge layers are damped more, the closer they are to the end
+    /// Absorbing ends additionally have a dashpot matched to the impedance of the medium,
+    /// so waves leave the chain without reflecting
+    fn pole_dampings(&self) -> Vec<f32> {
+        let n = self.angles.len();
+        let impedance = |i: usize| (self.rigidities[i] * self.inertias[i]).sqrt();
+        let damping = match self.settings.damping_model {
+            DampingModel::Viscous => self.settings.damping,
+            _ => 0.0,
+        };
+        let mut dampings = (0..n)
+            .map(|i| {
+                let sponge = self.settings.sponge_damping * sponge_profile(&self.settings, i, n);
+                (damping - sponge) * self.distance
+            })
+            .collect::<Vec<_>>();
+        if n > 0 {
+            if self.settings.bottom_boundary == Boundary::Absorbing {
+                dampings[0] -= impedance(0);
+            }
+            if self.settings.top_boundary == Boundary::Absorbing {
+                dampings[n - 1] -= impedance(n - 1);
+            }
+        }
+        dampings
+    }
+
+    /// Angular accelerations for given state
+    fn accelerations(&self, angles: &[f32], velocities: &[f32], time: f64) -> Vec<f32> {
+        self.torques_at(angles, velocities, time)
+            .into_iter()
+            .zip(self.pole_inertias())
+            .map(|(t, i)| t / i)
+            .collect()
+    }
+
+    /// Calculates total torque acting on every pole for given state
+    fn torques_at(&self, angles: &[f32], velocities: &[f32], time: f64) -> Vec<f32> {
+        let e = self.elastic_torques(angles);
+        let g = self.gravity_torques(angles);
+        let d = self.damping_torques(velocities);
+        let a = self.agitation_torques(time);
+        let h = self.grab_torques(angles, velocities);
+        (0..angles.len())
+            .map(|i| e[i] + g[i] + d[i] + a[i] + h[i])
+            .collect()
+    }
+
+    /// Torque pulling the grabbed pole, if any
+    fn grab_torques(&self, angles: &[f32], velocities: &[f32]) -> Vec<f32> {
+        let mut torques = vec![0.0; angles.len()];
+        if let Some(Grab { index, target }) = self.grab {
+            let inertia = self.inertias[index] * self.distance;
+            torques[index] = grab_torque(
+                angles[index],
+                velocities[index],
+                target,
+                inertia,
+                self.grab_frequency,
+            );
+        }
+        torques
+    }
+
+    /// Linear torques depending only on the angles: coupling between poles and restoring torque
+    fn elastic_torques(&self, angles: &[f32]) -> Vec<f32> {
+        let restoring = self.restoring_stiffness();
+        self.wave_torques(angles)
+            .into_iter()
+            .zip(angles)
+            .map(|(w, &angle)| w + restoring_torque(angle, restoring))
+            .collect()
+    }
+
+    /// Torsion constant pulling every pole back to its rest angle
+    /// Only the two-string model has it
+    fn restoring_stiffness(&self) -> f32 {
+        match self.settings.model {
+            Model::TwoString => self.settings.string_torsion * self.distance,
+            Model::SingleString | Model::SineGordon => 0.0,
+        }
+    }
+
+    /// Strength of the gravity-like torque on every pole
+    /// Only the sine-Gordon model has it
+    fn gravity_strength(&self) -> f32 {
+        match self.settings.model {
+            Model::SineGordon => self.settings.gravity * self.distance,
+            Model::SingleString | Model::TwoString => 0.0,
+        }
+    }
+
+    /// Nonlinear gravity-like torques of the sine-Gordon model
+    fn gravity_torques(&self, angles: &[f32]) -> Vec<f32> {
+        let gravity = self.gravity_strength();
+        angles.iter().map(|&a| gravity_torque(a, gravity)).collect()
+    }
+
+    /// Stiffness of the links between neighbours, torsional rigidity over their length
+    /// Link `i` connects pole `i - 1` with pole `i`, the first and the last link connect the end poles with anchors
+    /// If an end is not anchored (pole on that end is loose), its link has no stiffness
+    fn links(&self) -> Vec<f32> {
+        self.anchored_links(|i| self.rigidities[i] / self.distance)
+    }
+
+    /// Internal (Kelvin-Voigt) damping coefficients of the links, with the same layout as [`Self::links`]
+    fn internal_links(&self) -> Vec<f32> {
+        match self.settings.damping_model {
+            DampingModel::KelvinVoigt => {
+                self.anchored_links(|_| -self.settings.damping / self.distance)
+            }
+            _ => vec![0.0; self.angles.len() + 1],
+        }
+    }
+
+    /// Coefficients of all links, zeroed for ends without an anchor
+    /// `coefficient` receives the index of the pole above the link (or below for the top anchor link)
+    fn anchored_links(&self, coefficient: impl Fn(usize) -> f32) -> Vec<f32> {
+        let n = self.angles.len();
+        if n == 0 {
+            return vec![0.0];
+        }
+        (0..=n)
+            .map(|i| match i {
+                0 if !self.settings.bottom_boundary.anchored() => 0.0,
+                i if i == n && !self.settings.top_boundary.anchored() => 0.0,
+                i => coefficient(i.min(n - 1)),
+            })
+            .collect()
+    }
+
+    /// Wave-based torques, anchors are at angle 0
+    /// Anchors driven away from 0 are accounted for in agitation torques
+    fn wave_torques(&self, angles: &[f32]) -> Vec<f32> {
+        neighbour_torques(angles, &self.links())
+    }
+
+    /// All damping torques: viscous, internal and friction
+    fn damping_torques(&self, velocities: &[f32]) -> Vec<f32> {
+        let internal = neighbour_torques(velocities, &self.internal_links());
+        let friction = self.friction_torques(velocities);
+        velocities
+            .iter()
+            .zip(self.pole_dampings())
+            .enumerate()
+            .map(|(i, (&v, damping))| damping_torque(v, damping) + internal[i] + friction[i])
+            .collect()
+    }
+
+    /// Nonlinear damping torques of Coulomb friction and quadratic drag
+    fn friction_torques(&self, velocities: &[f32]) -> Vec<f32> {
+        let damping = self.settings.damping * self.distance;
+        velocities
+            .iter()
+            .map(|&v| match self.settings.damping_model {
+                DampingModel::Coulomb => coulomb_torque(v, damping),
+                DampingModel::Quadratic => drag_torque(v, damping),
+                DampingModel::Viscous | DampingModel::KelvinVoigt => 0.0,
+            })
+            .collect()
+    }
+
+    fn agitation_torques(&self, time: f64) -> Vec<f32> {
+        let n = self.angles.len();
+        let links = self.links();
+        let bottom = agitation_torque(
+            self.settings.bottom_boundary,
+            &self.settings.bottom_drive,
+            links[0],
+            time,
+        );
+        let top = agitation_torque(
+            self.settings.top_boundary,
+            &self.settings.top_drive,
+            links[n],
+            time,
+        );
+        let mut torques = vec![0.0; n];
+        if n > 0 {
+            torques[0] += bottom;
+            torques[n - 1] += top;
+        }
+        torques
+    }
+}
+
+/// Strength of sponge layers at the pole `index` out of `amount`, from 0 outside to 1 at the ends
+/// Rises quadratically, so waves entering the layer barely reflect
+pub fn sponge_profile(settings: &SoftSettings, index: usize, amount: usize) -> f32 {
+    let layer = |width: u32, depth: usize| match depth < width as usize {
+        true => ((width as usize - depth) as f32 / width as f32).powi(2),
+        false => 0.0,
+    };
+    let bottom = layer(settings.bottom_sponge, index);
+    let top = layer(settings.top_sponge, amount.saturating_sub(index + 1));
+    bottom.max(top)
+}
+
+/// `x += y * scale`
+fn add_scaled(x: &mut [f32], y: &[f32], scale: f32) {
+    x.iter_mut().zip(y).for_each(|(x, y)| *x += y * scale);
+}
+
+/// `x + y * scale`
+fn scaled_sum(x: &[f32], y: &[f32], scale: f32) -> Vec<f32> {
+    x.iter().zip(y).map(|(x, y)| x + y * scale).collect()
+}
+
+/// Solves a tridiagonal system with the Thomas algorithm
+/// `lower[0]` and `upper[n - 1]` are ignored
+fn solve_tridiagonal<T>(lower: &[T], diagonal: &[T], upper: &[T], mut rhs: Vec<T>) -> Vec<T>
+where
+    T: Copy + Default + Sub<Output = T> + Mul<Output = T> + Div<Output = T> + SubAssign,
+{
+    let n = rhs.len();
+    let mut upper_prime = vec![T::default(); n];
+    for i in 0..n {
+        let (previous_upper, previous_rhs) = match i {
+            0 => (T::default(), T::default()),
+            _ => (upper_prime[i - 1], rhs[i - 1]),
+        };
+        let denominator = diagonal[i] - lower[i] * previous_upper;
+        upper_prime[i] = upper[i] / denominator;
+        rhs[i] = (rhs[i] - lower[i] * previous_rhs) / denominator;
+    }
+    for i in (0..n.saturating_sub(1)).rev() {
+        let correction = upper_prime[i] * rhs[i + 1];
+        rhs[i] -= correction;
+    }
+    rhs
+}
+
+/// Torques of links pulling neighbouring values together, with link `i` between values `i - 1` and `i`
+/// Values beyond the ends are 0
+fn neighbour_torques(values: &[f32], links: &[f32]) -> Vec<f32> {
+    (0..values.len())
+        .map(|i| {
+            let below = i.checked_sub(1).map_or(0.0, |below| values[below]);
+            let above = values.get(i + 1).copied().unwrap_or(0.0);
+            wave_torque((below, values[i], above), (links[i], links[i + 1]))
+        })
+        .collect()
+}
+
+/// Calculates the wave-based torque from 3 neighbouring angles and stiffness of the links below and above
+fn wave_torque((below, current, above): (f32, f32, f32), (k_below, k_above): (f32, f32)) -> f32 {
+    k_below * (below - current) + k_above * (above - current)
+}
+
+/// Calculates harmonic torque returning the pole to angle 0
+fn restoring_torque(angle: f32, stiffness: f32) -> f32 {
+    -angle * stiffness
+}
+
+/// Calculates torque of a critically damped spring with natural angular frequency `frequency`,
+/// pulling a grabbed pole towards the target
+fn grab_torque(angle: f32, velocity: f32, target: f32, inertia: f32, frequency: f32) -> f32 {
+    inertia * frequency * (frequency * (target - angle) - 2.0 * velocity)
+}
+
+/// Calculates pendulum-like torque, which has stable equilibria every full turn
+fn gravity_torque(angle: f32, gravity: f32) -> f32 {
+    -angle.sin() * gravity
+}
+
+/// Calculates damping torque based on velocity and damping coefficient
+fn damping_torque(velocity: f32, damping: f32) -> f32 {
+    velocity * damping
+}
+
+/// Angular velocity below which Coulomb friction is scaled down, avoids chatter around rest
+const FRICTION_SMOOTHING: f32 = 0.01;
+
+/// Calculates velocity-independent friction torque
+fn coulomb_torque(velocity: f32, damping: f32) -> f32 {
+    (velocity / FRICTION_SMOOTHING).tanh() * damping
+}
+
+/// Calculates drag torque, quadratic in velocity
+fn drag_torque(velocity: f32, damping: f32) -> f32 {
+    velocity * velocity.abs() * damping
+}
+
+/// Calculates agitation torque on an edge-most (top or bottom) pole
+/// The drive is either a torque, or the angle of the anchor pulling the pole through its link
+fn agitation_torque(boundary: Boundary, drive: &Drive, link: f32, time: f64) -> f32 {
+    match boundary {
+        Boundary::Driven => drive.value(time) * link,
+        Boundary::Free | Boundary::Fixed | Boundary::Absorbing => drive.value(time),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poles::pole_distance;
+
+    /// Wave speed of the chains below, `sqrt(k / I)`
+    const SPEED: f32 = 2.0;
+
+    /// Undamped chain between fixed ends, with a Gaussian twist at `center` moving with `velocity`
+    fn pulse(
+        amount: usize,
+        distance: f32,
+        integrator: Integrator,
+        center: f32,
+        velocity: f32,
+    ) -> TorsionChain {
+        let soft_settings = SoftSettings {
+            stiffness: 1.0,
+            moment_of_inertia: 0.25,
+            damping: 0.0,
+            bottom_boundary: Boundary::Fixed,
+            top_boundary: Boundary::Fixed,
+            integrator,
+            ..Default::default()
+        };
+        let hard_settings = HardSettings {
+            distance,
+            ..Default::default()
+        };
+        let mut chain = TorsionChain::new(amount, &soft_settings, &hard_settings);
+        let width = 0.3;
+        for i in 0..amount {
+            let x = (i as f32 * distance - center) / width;
+            let angle = (-x * x / 2.0).exp();
+            chain.angles[i] = angle;
+            // Shape moving with `velocity`, `u(x - v t)` has `du/dt = -v du/dx`
+            chain.velocities[i] = velocity * x / width * angle;
+        }
+        chain
+    }
+
+    fn run(chain: &mut TorsionChain, dt: f32, duration: f32) {
+        for _ in 0..(duration / dt).round() as usize {
+            chain.step(dt);
+        }
+    }
+
+    #[test]
+    fn pulse_splits_into_symmetric_halves() {
+        let mut chain = pulse(41, 0.1, Integrator::SymplecticEuler, 2.0, 0.0);
+        run(&mut chain, 0.001, 0.5);
+        assert!(chain.angles.iter().all(|angle| angle.is_finite()));
+        // Both halves left the middle, travelling in opposite directions
+        assert!(chain.angles[20].abs() < 0.1, "{:?}", chain.angles);
+        for i in 0..20 {
+            assert!(
+                (chain.angles[i] - chain.angles[40 - i]).abs() < 1e-4,
+                "{:?}",
+                chain.angles
+            );
+        }
+        let peak = (0..20)
+            .max_by(|&a, &b| chain.angles[a].total_cmp(&chain.angles[b]))
+            .unwrap();
+        assert!(peak < 15, "peak at {}", peak);
+    }
+
+    #[test]
+    fn pulse_travels_at_wave_speed() {
+        // Wave speed must not depend on the pole spacing
+        for (amount, distance) in [(200, 0.05), (400, 0.025)] {
+            let mut chain = pulse(amount, distance, Integrator::VelocityVerlet, 2.5, SPEED);
+            let duration = 2.0;
+            run(&mut chain, 0.002, duration);
+            let peak = chain
+                .angles
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .unwrap()
+                .0;
+            let position = peak as f32 * distance;
+            let expected = 2.5 + SPEED * duration;
+            assert!(
+                (position - expected).abs() <= 2.0 * distance,
+                "spacing {}: peak at {}, expected {}",
+                distance,
+                position,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn energy_is_conserved_without_damping() {
+        for integrator in Integrator::ALL {
+            let mut chain = pulse(32, 0.1, integrator, 1.5, 0.0);
+            let initial = chain.energy().total();
+            run(&mut chain, 0.001, 2.0);
+            let drift = (chain.energy().total() - initial).abs() / initial;
+            assert!(drift < 0.002, "{}: drift {}", integrator.name(), drift);
+        }
+    }
+
+    #[test]
+    fn energy_balances_power_in_and_out() {
+        // Ends driven by angle and by torque, into a damped chain
+        let dt = 0.0005;
+        for damping_model in DampingModel::ALL {
+            for integrator in Integrator::ALL {
+                let mut chain = pulse(32, 0.1, integrator, 1.5, 0.0);
+                chain.settings.damping_model = damping_model;
+                chain.settings.damping = -0.5;
+                chain.settings.bottom_boundary = Boundary::Driven;
+                chain.settings.bottom_drive = Drive::Sine {
+                    frequency: 0.5,
+                    phase: 0.0,
+                    amplitude: 0.3,
+                };
+                chain.settings.top_boundary = Boundary::Free;
+                chain.settings.top_drive = Drive::Sine {
+                    frequency: 0.7,
+                    phase: 0.0,
+                    amplitude: 0.2,
+                };
+                let initial = chain.energy();
+                // Trapezoidal integral of the net power, and of the power flowing either way
+                let mut previous = initial;
+                let mut net = 0.0;
+                let mut flow = 0.0;
+                for _ in 0..(4.0 / dt) as usize {
+                    chain.step(dt);
+                    let energy = chain.energy();
+                    let power = |e: Energy| e.injected - e.dissipated;
+                    net += (power(previous) + power(energy)) / 2.0 * dt;
+                    flow += (previous.injected.abs() + previous.dissipated.abs()) * dt;
+                    previous = energy;
+                }
+                let change = previous.total() - initial.total();
+                let error = (change - net).abs() / flow;
+                assert!(
+                    error < 0.001,
+                    "{} {}: energy changed by {}, net power delivered {}",
+                    damping_model.name(),
+                    integrator.name(),
+                    change,
+                    net
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn sine_drive_settles_into_steady_state() {
+        for integrator in Integrator::ALL {
+            let mut chain = pulse(32, 0.1, integrator, 1.5, 0.0);
+            chain.angles.fill(0.0);
+            chain.velocities.fill(0.0);
+            chain.settings.damping = -2.0;
+            chain.settings.bottom_boundary = Boundary::Driven;
+            chain.settings.bottom_drive = Drive::Sine {
+                frequency: 0.5,
+                phase: 0.3,
+                amplitude: 0.05,
+            };
+            // Transients decay by a factor of `e^-12` meanwhile
+            run(&mut chain, 0.001, 30.0);
+            let expected = chain.steady_state(chain.time).unwrap();
+            let scale = expected.iter().fold(0.0f32, |max, a| max.max(a.abs()));
+            let difference = chain
+                .angles
+                .iter()
+                .zip(&expected)
+                .fold(0.0f32, |max, (a, b)| max.max((a - b).abs()));
+            assert!(
+                difference < 0.01 * scale,
+                "{}: difference {}, amplitude {}",
+                integrator.name(),
+                difference,
+                scale
+            );
+        }
+    }
+
+    #[test]
+    fn absorbing_end_does_not_reflect() {
+        let remaining = |boundary| {
+            let mut chain = pulse(200, 0.05, Integrator::VelocityVerlet, 5.0, SPEED);
+            chain.settings.top_boundary = boundary;
+            let initial = chain.energy().total();
+            // The pulse reaches the top after 2.5 s, a reflection would be back inside the chain
+            run(&mut chain, 0.002, 4.0);
+            chain.energy().total() / initial
+        };
+        let absorbed = remaining(Boundary::Absorbing);
+        let reflected = remaining(Boundary::Free);
+        assert!(absorbed < 0.03, "absorbing end kept {}", absorbed);
+        assert!(reflected > 0.99, "free end kept {}", reflected);
+    }
+
+    #[test]
+    fn grab_is_stable_at_longest_step() {
+        // Longest step allowed by the settings window
+        let dt = 0.01;
+        let hard_settings = HardSettings {
+            distance: pole_distance(32),
+            ..Default::default()
+        };
+        for integrator in Integrator::ALL {
+            let soft_settings = SoftSettings {
+                integrator,
+                ..Default::default()
+            };
+            let mut chain = TorsionChain::new(32, &soft_settings, &hard_settings);
+            chain.grab = Some(Grab {
+                index: 16,
+                target: 1.0,
+            });
+            run(&mut chain, dt, 5.0);
+            assert!(
+                chain.angles.iter().all(|angle| angle.is_finite()),
+                "{}",
+                integrator.name()
+            );
+            assert!(
+                (chain.angles[16] - 1.0).abs() < 0.05,
+                "{}: grabbed pole at {}",
+                integrator.name(),
+                chain.angles[16]
+            );
+        }
+    }
+
+    #[test]
+    fn integrators_agree() {
+        let mut reference = pulse(32, 0.1, Integrator::RungeKutta4, 1.5, 0.0);
+        run(&mut reference, 0.0005, 1.0);
+        for integrator in Integrator::ALL {
+            let mut chain = pulse(32, 0.1, integrator, 1.5, 0.0);
+            run(&mut chain, 0.0005, 1.0);
+            let difference = chain
+                .angles
+                .iter()
+                .zip(&reference.angles)
+                .fold(0.0f32, |max, (a, b)| max.max((a - b).abs()));
+            assert!(
+                difference < 0.002,
+                "{}: difference {}",
+                integrator.name(),
+                difference
+            );
+        }
+    }
+
+    #[test]
+    fn tridiagonal_solution() {
+        let lower = [0.0f32, 1.0, 1.0, 1.0];
+        let diagonal = [4.0, 4.0, 4.0, 4.0];
+        let upper = [1.0, 1.0, 1.0, 0.0];
+        let rhs = vec![6.0, 12.0, 18.0, 19.0];
+        let solution = solve_tridiagonal(&lower, &diagonal, &upper, rhs);
+        for (x, expected) in solution.iter().zip([1.0, 2.0, 3.0, 4.0]) {
+            assert!((x - expected).abs() < 1e-5, "{:?}", solution);
+        }
+    }
+}
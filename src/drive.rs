@@ -0,0 +1,292 @@
+//! Driving waveforms for the chain ends
+
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+
+use crate::noise;
+
+/// Waveform driving one end of the chain
+/// `amplitude` is the peak torque, or the peak angle for ends with driven angle
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Drive {
+    Sine {
+        frequency: f32,
+        phase: f32,
+        amplitude: f32,
+    },
+    Square {
+        frequency: f32,
+        phase: f32,
+        amplitude: f32,
+    },
+    Triangle {
+        frequency: f32,
+        phase: f32,
+        amplitude: f32,
+    },
+    Sawtooth {
+        frequency: f32,
+        phase: f32,
+        amplitude: f32,
+    },
+    /// Single Gaussian pulse peaking at `delay`
+    Pulse {
+        delay: f32,
+        width: f32,
+        amplitude: f32,
+    },
+    /// Sine with frequency changing linearly over `duration`, then held
+    Chirp {
+        start_frequency: f32,
+        end_frequency: f32,
+        duration: f32,
+        amplitude: f32,
+    },
+    /// White noise, with a new random value `bandwidth` times per second
+    Noise {
+        bandwidth: f32,
+        seed: u32,
+        amplitude: f32,
+    },
+}
+
+impl Default for Drive {
+    fn default() -> Self {
+        Self::Sine {
+            frequency: 0.0,
+            phase: 0.0,
+            amplitude: 0.0,
+        }
+    }
+}
+
+impl Drive {
+    /// Every kind of drive with default parameters and given amplitude
+    pub fn presets(amplitude: f32) -> [Self; 7] {
+        [
+            Self::Sine {
+                frequency: 0.2,
+                phase: 0.0,
+                amplitude,
+            },
+            Self::Square {
+                frequency: 0.2,
+                phase: 0.0,
+                amplitude,
+            },
+            Self::Triangle {
+                frequency: 0.2,
+                phase: 0.0,
+                amplitude,
+            },
+            Self::Sawtooth {
+                frequency: 0.2,
+                phase: 0.0,
+                amplitude,
+            },
+            Self::Pulse {
+                delay: 1.0,
+                width: 0.1,
+                amplitude,
+            },
+            Self::Chirp {
+                start_frequency: 0.1,
+                end_frequency: 2.0,
+                duration: 30.0,
+                amplitude,
+            },
+            Self::Noise {
+                bandwidth: 10.0,
+                seed: 0,
+                amplitude,
+            },
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sine { .. } => "Sine",
+            Self::Square { .. } => "Square",
+            Self::Triangle { .. } => "Triangle",
+            Self::Sawtooth { .. } => "Sawtooth",
+            Self::Pulse { .. } => "Gaussian pulse",
+            Self::Chirp { .. } => "Chirp",
+            Self::Noise { .. } => "White noise",
+        }
+    }
+
+    /// Whether both drives have the same waveform, regardless of parameters
+    pub fn same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    /// Sets the frequency of periodic waveforms, other waveforms are replaced by a sine
+    pub fn tune(&mut self, frequency: f32) {
+        match self {
+            Self::Sine { frequency: f, .. }
+            | Self::Square { frequency: f, .. }
+            | Self::Triangle { frequency: f, .. }
+            | Self::Sawtooth { frequency: f, .. } => *f = frequency,
+            Self::Pulse { amplitude, .. }
+            | Self::Chirp { amplitude, .. }
+            | Self::Noise { amplitude, .. } => {
+                *self = Self::Sine {
+                    frequency,
+                    phase: 0.0,
+                    amplitude: *amplitude,
+                }
+            }
+        }
+    }
+
+    pub fn amplitude(&self) -> f32 {
+        match *self {
+            Self::Sine { amplitude, .. }
+            | Self::Square { amplitude, .. }
+            | Self::Triangle { amplitude, .. }
+            | Self::Sawtooth { amplitude, .. }
+            | Self::Pulse { amplitude, .. }
+            | Self::Chirp { amplitude, .. }
+            | Self::Noise { amplitude, .. } => amplitude,
+        }
+    }
+
+    pub fn amplitude_mut(&mut self) -> &mut f32 {
+        match self {
+            Self::Sine { amplitude, .. }
+            | Self::Square { amplitude, .. }
+            | Self::Triangle { amplitude, .. }
+            | Self::Sawtooth { amplitude, .. }
+            | Self::Pulse { amplitude, .. }
+            | Self::Chirp { amplitude, .. }
+            | Self::Noise { amplitude, .. } => amplitude,
+        }
+    }
+
+    /// Value of the drive at given time
+    pub fn value(&self, time: f64) -> f32 {
+        let cycles = |frequency: f32, phase: f32| time * frequency as f64 + phase as f64 / TAU;
+        let fraction = |frequency: f32, phase: f32| cycles(frequency, phase).rem_euclid(1.0);
+        let shape = match *self {
+            Self::Sine {
+                frequency, phase, ..
+            } => (cycles(frequency, phase) * TAU).sin(),
+            Self::Square {
+                frequency, phase, ..
+            } => match fraction(frequency, phase) < 0.5 {
+                true => 1.0,
+                false => -1.0,
+            },
+            Self::Triangle {
+                frequency, phase, ..
+            } => 4.0 * ((fraction(frequency, phase) + 0.75).rem_euclid(1.0) - 0.5).abs() - 1.0,
+            Self::Sawtooth {
+                frequency, phase, ..
+            } => 2.0 * (fraction(frequency, phase) + 0.5).rem_euclid(1.0) - 1.0,
+            Self::Pulse { delay, width, .. } => {
+                let x = (time - delay as f64) / width as f64;
+                (-x * x / 2.0).exp()
+            }
+            Self::Chirp {
+                start_frequency,
+                end_frequency,
+                duration,
+                ..
+            } => {
+                let (f0, f1, d) = (
+                    start_frequency as f64,
+                    end_frequency as f64,
+                    duration as f64,
+                );
+                let sweep = time.min(d);
+                let cycles =
+                    f0 * sweep + (f1 - f0) * sweep * sweep / (2.0 * d) + f1 * (time - sweep);
+                (cycles * TAU).sin()
+            }
+            Self::Noise {
+                bandwidth, seed, ..
+            } => {
+                let sample = (time * bandwidth as f64).floor() as i64;
+                noise::uniform(seed as u64, sample as u64) as f64
+            }
+        };
+        shape as f32 * self.amplitude()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frequency from the spacing of the first two zero crossings after `time`, with the time between them
+    fn frequency_at(drive: &Drive, time: f64) -> (f64, f64) {
+        let dt = 1e-4;
+        let mut crossings = Vec::new();
+        let mut t = time;
+        while crossings.len() < 2 {
+            if drive.value(t).signum() != drive.value(t + dt).signum() {
+                crossings.push(t);
+            }
+            t += dt;
+        }
+        let middle = (crossings[0] + crossings[1]) / 2.0;
+        (middle, 0.5 / (crossings[1] - crossings[0]))
+    }
+
+    #[test]
+    fn pulse_peaks_at_delay() {
+        let drive = Drive::Pulse {
+            delay: 2.0,
+            width: 0.5,
+            amplitude: 3.0,
+        };
+        assert!((drive.value(2.0) - 3.0).abs() < 1e-6);
+        // One width away from the peak the Gaussian drops to `e^(-1/2)`
+        let edge = 3.0 * (-0.5f32).exp();
+        assert!((drive.value(1.5) - edge).abs() < 1e-5);
+        assert!((drive.value(2.5) - edge).abs() < 1e-5);
+        assert!(drive.value(-0.5) < 1e-4);
+        assert!(drive.value(4.5) < 1e-4);
+    }
+
+    #[test]
+    fn chirp_sweeps_linearly_then_holds() {
+        let drive = Drive::Chirp {
+            start_frequency: 1.0,
+            end_frequency: 3.0,
+            duration: 10.0,
+            amplitude: 1.0,
+        };
+        for time in [0.5, 5.0, 9.0, 15.0] {
+            let (time, frequency) = frequency_at(&drive, time);
+            let expected = 1.0 + 2.0 * time.min(10.0) / 10.0;
+            assert!(
+                (frequency - expected).abs() < 0.02 * expected,
+                "at {} s: {} 1 / s, expected {}",
+                time,
+                frequency,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn noise_is_deterministic_per_seed() {
+        let noise = |seed| Drive::Noise {
+            bandwidth: 10.0,
+            seed,
+            amplitude: 2.0,
+        };
+        let samples = |drive: Drive| {
+            (0..100)
+                .map(|i| drive.value(i as f64 * 0.1))
+                .collect::<Vec<_>>()
+        };
+        assert!(samples(noise(1)) == samples(noise(1)));
+        assert!(samples(noise(1)) != samples(noise(2)));
+        assert!(samples(noise(1)).iter().all(|value| value.abs() <= 2.0));
+        // Held for a whole sample
+        assert_eq!(noise(1).value(0.51), noise(1).value(0.59));
+    }
+}
@@ -0,0 +1,57 @@
+//! Energy accounting of the chain
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    chain::{Energy, TorsionChain},
+    scaled_time::ScaledTime,
+};
+
+/// Span of simulated time kept for plotting
+const HISTORY_DURATION: f64 = 20.0;
+
+/// Recent energy of the chain, sampled once per frame
+#[derive(Default)]
+pub struct EnergyHistory {
+    pub samples: VecDeque<(f64, Energy)>,
+}
+
+impl EnergyHistory {
+    pub fn latest(&self) -> Energy {
+        self.samples
+            .back()
+            .map(|(_, energy)| *energy)
+            .unwrap_or_default()
+    }
+}
+
+fn sample(
+    mut history: ResMut<EnergyHistory>,
+    chain: Res<TorsionChain>,
+    scaled_time: Res<ScaledTime>,
+) {
+    if !scaled_time.ticked() {
+        return;
+    }
+    // Time jumps backwards after rewinding, resetting or loading a snapshot
+    if let Some((time, _)) = history.samples.back() {
+        if *time > chain.time {
+            history.samples.clear();
+        }
+    }
+    history.samples.push_back((chain.time, chain.energy()));
+    while history.samples.front().unwrap().0 < chain.time - HISTORY_DURATION {
+        history.samples.pop_front();
+    }
+}
+
+pub struct EnergyPlugin;
+
+impl Plugin for EnergyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EnergyHistory>()
+            .add_system(sample.after("physics"));
+    }
+}
@@ -83,7 +83,7 @@ fn player_look(
     mut query: Query<&mut Transform, With<FlyCam>>,
 ) {
     let window = windows.get_primary().unwrap();
-    let mut delta_state = state.as_mut();
+    let delta_state = state.as_mut();
     for mut transform in query.iter_mut() {
         for ev in delta_state.reader_motion.iter(&motion) {
             if window.cursor_locked() {
@@ -0,0 +1,119 @@
+//! Grabbing and twisting poles with the mouse
+
+use bevy::prelude::*;
+use bevy_egui::EguiContext;
+
+use crate::{
+    flycam::FlyCam,
+    poles::{pole_side, Pole},
+    settings::HardSettings,
+    wave::Angle,
+};
+
+/// Twist per pixel of horizontal mouse movement
+const SENSITIVITY: f32 = 0.02;
+
+/// Marks the pole held by the user, pulled towards the target angle
+#[derive(Component)]
+pub struct Grabbed {
+    pub target: f32,
+    /// Angle of the pole when grabbed
+    start_angle: f32,
+    /// Horizontal cursor position when grabbed
+    start_cursor: f32,
+}
+
+/// Distance along the ray to the closest intersection with a box centered at origin
+/// Slab method, the ray is in the box's local space
+fn intersect_box(origin: Vec3, direction: Vec3, half_extents: Vec3) -> Option<f32> {
+    let inverse = direction.recip();
+    let t1 = (-half_extents - origin) * inverse;
+    let t2 = (half_extents - origin) * inverse;
+    let near = t1.min(t2).max_element();
+    let far = t1.max(t2).min_element();
+    (near <= far && far >= 0.).then(|| near.max(0.))
+}
+
+/// Picks the pole under the cursor on left click
+fn pick(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    buttons: Res<Input<MouseButton>>,
+    mut egui_context: ResMut<EguiContext>,
+    cameras: Query<(&Camera, &GlobalTransform), With<FlyCam>>,
+    poles: Query<(Entity, &Transform, &Angle), With<Pole>>,
+    settings: Res<HardSettings>,
+) {
+    if !buttons.just_pressed(MouseButton::Left) || egui_context.ctx_mut().wants_pointer_input() {
+        return;
+    }
+    let window = windows.get_primary().unwrap();
+    let cursor = match window.cursor_position() {
+        Some(cursor) if !window.cursor_locked() => cursor,
+        _ => return,
+    };
+    let (camera, camera_transform) = match cameras.get_single() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    // Ray from the camera through the cursor
+    let ndc = cursor / Vec2::new(window.width(), window.height()) * 2. - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix.inverse();
+    let origin = camera_transform.translation;
+    let direction = (ndc_to_world.project_point3(ndc.extend(0.5)) - origin).normalize();
+
+    let side = pole_side(settings.amount);
+    let half_extents = Vec3::new(settings.length * side, side, side) / 2.;
+    let closest = poles
+        .iter()
+        .filter_map(|(entity, transform, angle)| {
+            let rotation = transform.rotation.inverse();
+            let local_origin = rotation * (origin - transform.translation);
+            let local_direction = rotation * direction;
+            intersect_box(local_origin, local_direction, half_extents)
+                .map(|distance| (entity, angle.0, distance))
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2));
+
+    if let Some((entity, angle, _)) = closest {
+        commands.entity(entity).insert(Grabbed {
+            target: angle,
+            start_angle: angle,
+            start_cursor: cursor.x,
+        });
+    }
+}
+
+/// Twists the grabbed pole with horizontal mouse movement
+fn drag(windows: Res<Windows>, mut query: Query<&mut Grabbed>) {
+    let window = windows.get_primary().unwrap();
+    if let Some(cursor) = window.cursor_position() {
+        for mut grabbed in query.iter_mut() {
+            grabbed.target = grabbed.start_angle + (cursor.x - grabbed.start_cursor) * SENSITIVITY;
+        }
+    }
+}
+
+/// Lets go of the grabbed pole when the button is released
+fn release(
+    mut commands: Commands,
+    buttons: Res<Input<MouseButton>>,
+    query: Query<Entity, With<Grabbed>>,
+) {
+    if buttons.just_released(MouseButton::Left) {
+        for entity in query.iter() {
+            commands.entity(entity).remove::<Grabbed>();
+        }
+    }
+}
+
+pub struct GrabPlugin;
+
+impl Plugin for GrabPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(pick.before("physics"))
+            .add_system(drag.before("physics"))
+            .add_system(release.before("physics"));
+    }
+}
@@ -0,0 +1,138 @@
+//! Batch runs from the command line, without a window or rendering
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    medium::MediumPlugin,
+    poles::{Pole, PolePlugin},
+    presets::Preset,
+    scaled_time::{ScaledTime, ScaledTimePlugin},
+    settings::SettingsPlugin,
+    snapshot::{PendingSnapshot, Snapshot, SnapshotPlugin},
+    wave::{Angle, WavePlugin},
+};
+
+pub const USAGE: &str = "\
+Usage: torsion-waves run [options]
+
+Options:
+    --preset <name or path>  Built-in preset or preset file to start from
+    --snapshot <path>        Snapshot file to start from, overrides the preset
+    --steps <n>              Amount of physics steps to run (default 1000)
+    --every <n>              Write a row every n steps (default 1)
+    --out <path>             CSV file for the angles of the poles (default stdout)";
+
+struct Options {
+    preset: Preset,
+    snapshot: Option<Snapshot>,
+    steps: u64,
+    every: u64,
+    out: Option<String>,
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        preset: Preset::default(),
+        snapshot: None,
+        steps: 1000,
+        every: 1,
+        out: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value of `{}`", arg))
+        };
+        let number = |value: &String| {
+            value
+                .parse::<u64>()
+                .map_err(|e| format!("Invalid value of `{}`: {}", arg, e))
+        };
+        match arg.as_str() {
+            "--preset" => options.preset = Preset::find(value()?)?,
+            "--snapshot" => options.snapshot = Some(Snapshot::load(value()?)?),
+            "--steps" => options.steps = number(value()?)?,
+            "--every" => options.every = number(value()?)?.max(1),
+            "--out" => options.out = Some(value()?.clone()),
+            _ => return Err(format!("Unknown argument `{}`", arg)),
+        }
+    }
+    if let Some(snapshot) = &options.snapshot {
+        options.preset = snapshot.preset.clone();
+    }
+    Ok(options)
+}
+
+/// Writes the current time and angles of all poles as a CSV row
+fn write_row(world: &mut World, out: &mut impl Write) -> io::Result<()> {
+    let time = world.resource::<ScaledTime>().total;
+    let mut angles = world
+        .query::<(&Pole, &Angle)>()
+        .iter(world)
+        .map(|(pole, angle)| (pole.index, angle.0))
+        .collect::<Vec<_>>();
+    angles.sort_unstable_by_key(|(index, _)| *index);
+    write!(out, "{}", time)?;
+    for (_, angle) in angles {
+        write!(out, ",{}", angle)?;
+    }
+    writeln!(out)
+}
+
+/// Application without rendering, physics only advances by explicit steps
+pub fn app(preset: Preset, snapshot: Option<Snapshot>) -> App {
+    let mut app = App::new();
+    app.insert_resource(preset.soft)
+        .insert_resource(preset.hard)
+        .insert_resource(PendingSnapshot(snapshot))
+        .add_plugins(MinimalPlugins)
+        .add_plugin(SettingsPlugin)
+        .add_plugin(PolePlugin)
+        .add_plugin(WavePlugin)
+        .add_plugin(MediumPlugin)
+        .add_plugin(SnapshotPlugin)
+        .add_plugin(ScaledTimePlugin);
+    app.world.resource_mut::<ScaledTime>().paused = true;
+    app
+}
+
+/// Runs the physics for a fixed amount of steps, dumping the time series of angles
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse(args)?;
+    let mut out: Box<dyn Write> = match &options.out {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| e.to_string())?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    let amount = options.preset.hard.amount;
+    let mut app = app(options.preset, options.snapshot);
+
+    let header = (0..amount)
+        .map(|i| format!(",angle_{}", i))
+        .collect::<String>();
+    writeln!(out, "time{}", header).map_err(|e| e.to_string())?;
+    simulate(&mut app, options.steps, options.every, &mut out).map_err(|e| e.to_string())
+}
+
+/// Steps the application one physics step per update
+fn simulate(app: &mut App, steps: u64, every: u64, out: &mut impl Write) -> io::Result<()> {
+    // Spawns the poles and restores the snapshot
+    app.update();
+    write_row(&mut app.world, out)?;
+    for step in 1..=steps {
+        app.world.resource_mut::<ScaledTime>().step(1);
+        app.update();
+        if step % every == 0 {
+            write_row(&mut app.world, out)?;
+        }
+    }
+    out.flush()
+}
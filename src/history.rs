@@ -0,0 +1,87 @@
+//! Recording past chain states for rewinding a paused simulation
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    chain::TorsionChain,
+    poles::Pole,
+    scaled_time::ScaledTime,
+    wave::{Angle, AngularVelocity},
+};
+
+/// Span of simulated time kept in the history
+const HISTORY_DURATION: f64 = 10.0;
+
+/// State of the chain at some moment
+pub struct Frame {
+    pub time: f64,
+    angles: Vec<f32>,
+    velocities: Vec<f32>,
+}
+
+/// Past chain states, one per rendered frame in which physics advanced
+#[derive(Default)]
+pub struct History {
+    pub frames: VecDeque<Frame>,
+    /// Frame currently shown, `None` when at the present
+    pub cursor: Option<usize>,
+}
+
+/// Stores the chain state after physics, discarding the future if the user rewound
+fn record(mut history: ResMut<History>, chain: Res<TorsionChain>, scaled_time: Res<ScaledTime>) {
+    if !scaled_time.ticked() {
+        return;
+    }
+    if let Some(cursor) = history.cursor.take() {
+        history.frames.truncate(cursor + 1);
+    }
+    history.frames.push_back(Frame {
+        time: chain.time,
+        angles: chain.angles.clone(),
+        velocities: chain.velocities.clone(),
+    });
+    while history.frames.front().unwrap().time < chain.time - HISTORY_DURATION {
+        history.frames.pop_front();
+    }
+}
+
+/// Moves the poles to the frame selected by the cursor
+fn rewind(
+    history: Res<History>,
+    mut scaled_time: ResMut<ScaledTime>,
+    mut query: Query<(&Pole, &mut Angle, &mut AngularVelocity)>,
+) {
+    if !history.is_changed() {
+        return;
+    }
+    let frame = match history.cursor.and_then(|cursor| history.frames.get(cursor)) {
+        Some(frame) => frame,
+        None => return,
+    };
+    scaled_time.total = frame.time;
+    for (pole, mut angle, mut angular_velocity) in query.iter_mut() {
+        angle.0 = frame.angles[pole.index];
+        angular_velocity.0 = frame.velocities[pole.index];
+    }
+}
+
+/// Forgets the history of a chain that was reset
+fn clear(mut history: ResMut<History>, added: Query<(), Added<Pole>>) {
+    if !added.is_empty() {
+        history.frames.clear();
+        history.cursor = None;
+    }
+}
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>()
+            .add_system(clear.label("clear-history").before("physics"))
+            .add_system(rewind.after("clear-history").before("physics"))
+            .add_system(record.after("physics"));
+    }
+}
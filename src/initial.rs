@@ -0,0 +1,50 @@
+//! Initial state of the chain after reset
+
+use std::f32::consts::TAU;
+
+use crate::{
+    modes::{self, Anchors},
+    noise,
+    settings::{HardSettings, InitialState, SoftSettings},
+};
+
+/// Angle and angular velocity of the pole at `index` right after reset
+pub fn initial_state(
+    hard_settings: &HardSettings,
+    soft_settings: &SoftSettings,
+    index: usize,
+) -> (f32, f32) {
+    let amplitude = hard_settings.initial_amplitude;
+    let width = hard_settings.initial_width;
+    let length = (hard_settings.amount as f32 - 1.) * hard_settings.distance;
+    let x = index as f32 * hard_settings.distance - hard_settings.initial_position * length;
+    let speed = (soft_settings.stiffness / soft_settings.moment_of_inertia).sqrt();
+    let gaussian = (-x * x / (2. * width * width)).exp();
+
+    match hard_settings.initial_state {
+        InitialState::Rest => (0., 0.),
+        InitialState::Pulse => (amplitude * gaussian, 0.),
+        InitialState::StandingWave => {
+            let shape = modes::shape(
+                hard_settings.initial_mode,
+                index,
+                hard_settings.amount,
+                Anchors::new(soft_settings),
+            );
+            (amplitude * shape, 0.)
+        }
+        InitialState::Packet => {
+            // Travels upwards: `θ(x - vt)` gives `ω = -v θ'`
+            let k = TAU * hard_settings.initial_mode as f32 / length.max(f32::EPSILON);
+            let angle = amplitude * gaussian * (k * x).cos();
+            let slope =
+                -amplitude * gaussian * (x / (width * width) * (k * x).cos() + k * (k * x).sin());
+            (angle, -speed * slope)
+        }
+        InitialState::Noise => {
+            let seed = hard_settings.initial_seed as u64;
+            (amplitude * noise::uniform(seed, index as u64), 0.)
+        }
+        InitialState::Twist => (amplitude * (1. + (x / width).tanh()) / 2., 0.),
+    }
+}
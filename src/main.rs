@@ -1,20 +1,74 @@
+mod analysis;
+mod chain;
+mod complex;
+mod drive;
+mod energy;
 mod flycam;
+mod grab;
+mod headless;
+mod history;
+mod initial;
+mod medium;
+mod modes;
+mod noise;
 mod poles;
+mod presets;
+mod recorder;
 mod scaled_time;
 mod settings;
+mod snapshot;
+mod soliton;
+mod strings;
 mod ui;
+mod waterfall;
 mod wave;
 
+use analysis::AnalysisPlugin;
 use bevy::prelude::*;
+use energy::EnergyPlugin;
 use flycam::{FlyCam, FlycamPlugin};
-use poles::PolePlugin;
+use grab::GrabPlugin;
+use history::HistoryPlugin;
+use medium::MediumPlugin;
+use poles::{PoleMeshPlugin, PolePlugin};
+use presets::Preset;
+use recorder::RecorderPlugin;
 use scaled_time::ScaledTimePlugin;
 use settings::SettingsPlugin;
+use snapshot::SnapshotPlugin;
+use soliton::SolitonPlugin;
+use strings::StringPlugin;
 use ui::UIPlugin;
+use waterfall::WaterfallPlugin;
 use wave::WavePlugin;
 
+/// Preset selected with `--preset <name or path>`
+fn startup_preset(args: &[String]) -> Option<Preset> {
+    let mut args = args.iter().skip_while(|arg| *arg != "--preset");
+    let name = args.nth(1)?;
+    match Preset::find(name) {
+        Ok(preset) => Some(preset),
+        Err(error) => {
+            eprintln!("Cannot load preset `{}`: {}", name, error);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("run") {
+        if let Err(error) = headless::run(&args[1..]) {
+            eprintln!("{}\n\n{}", error, headless::USAGE);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let preset = startup_preset(&args).unwrap_or_default();
     App::new()
+        .insert_resource(preset.soft)
+        .insert_resource(preset.hard)
         .insert_resource(Msaa { samples: 4 })
         .insert_resource(ClearColor(Color::rgb_u8(0x8C, 0xC0, 0xDE)))
         .add_plugins(DefaultPlugins)
@@ -22,7 +76,18 @@ fn main() {
         .add_plugin(UIPlugin)
         .add_plugin(SettingsPlugin)
         .add_plugin(PolePlugin)
+        .add_plugin(PoleMeshPlugin)
         .add_plugin(WavePlugin)
+        .add_plugin(MediumPlugin)
+        .add_plugin(SolitonPlugin)
+        .add_plugin(GrabPlugin)
+        .add_plugin(HistoryPlugin)
+        .add_plugin(SnapshotPlugin)
+        .add_plugin(RecorderPlugin)
+        .add_plugin(EnergyPlugin)
+        .add_plugin(WaterfallPlugin)
+        .add_plugin(AnalysisPlugin)
+        .add_plugin(StringPlugin)
         .add_plugin(ScaledTimePlugin)
         .add_startup_system(setup)
         .run();
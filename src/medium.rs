@@ -0,0 +1,106 @@
+//! Physical properties of individual poles and links
+//! Allows inhomogeneous media, derived from settings and a preset
+
+use bevy::prelude::*;
+
+use crate::{
+    noise,
+    poles::Pole,
+    settings::{Medium, SoftSettings},
+};
+
+/// Moment of inertia per unit length of a pole
+#[derive(Component)]
+pub struct Inertia(pub f32);
+
+/// Torsional rigidity of the link between a pole and the one below (or the bottom anchor)
+/// The top anchor link and impedance of the top end use the rigidity of the top-most pole
+#[derive(Component)]
+pub struct Stiffness(pub f32);
+
+/// Largest strength of disorder, keeps inertia and stiffness positive
+pub const MAX_DISORDER: f32 = 0.9;
+
+/// Multipliers of inertia and stiffness of the pole at `index` out of `amount`
+pub fn profile(settings: &SoftSettings, index: usize, amount: usize) -> (f32, f32) {
+    let position = match amount {
+        0 | 1 => 0.0,
+        _ => index as f32 / (amount - 1) as f32,
+    };
+    let blend = |ratio: f32, t: f32| 1.0 + (ratio - 1.0) * t;
+    match settings.medium {
+        Medium::Uniform => (1.0, 1.0),
+        Medium::Step => match index >= amount / 2 {
+            true => (settings.inertia_ratio, settings.stiffness_ratio),
+            false => (1.0, 1.0),
+        },
+        Medium::Gradient => (
+            blend(settings.inertia_ratio, position),
+            blend(settings.stiffness_ratio, position),
+        ),
+        Medium::Disorder => {
+            let seed = settings.disorder_seed as u64;
+            // Stronger disorder, e.g. from a preset file, could make the factors vanish
+            let disorder = settings.disorder.clamp(0.0, MAX_DISORDER);
+            (
+                1.0 + disorder * noise::uniform(seed, 2 * index as u64),
+                1.0 + disorder * noise::uniform(seed, 2 * index as u64 + 1),
+            )
+        }
+    }
+}
+
+/// Settings the pole properties are derived from
+#[derive(PartialEq)]
+struct Parameters {
+    moment_of_inertia: f32,
+    stiffness: f32,
+    medium: Medium,
+    inertia_ratio: f32,
+    stiffness_ratio: f32,
+    disorder: f32,
+    disorder_seed: u32,
+}
+
+impl Parameters {
+    fn new(settings: &SoftSettings) -> Self {
+        Self {
+            moment_of_inertia: settings.moment_of_inertia,
+            stiffness: settings.stiffness,
+            medium: settings.medium,
+            inertia_ratio: settings.inertia_ratio,
+            stiffness_ratio: settings.stiffness_ratio,
+            disorder: settings.disorder,
+            disorder_seed: settings.disorder_seed,
+        }
+    }
+}
+
+/// Recalculates pole properties when relevant settings change or poles are respawned
+/// The settings window touches the settings every frame, so they are compared with the last used ones
+fn update(
+    mut query: Query<(&Pole, &mut Inertia, &mut Stiffness)>,
+    added: Query<(), Added<Pole>>,
+    settings: Res<SoftSettings>,
+    mut last: Local<Option<Parameters>>,
+) {
+    let parameters = Some(Parameters::new(&settings));
+    if *last == parameters && added.is_empty() {
+        return;
+    }
+    *last = parameters;
+    let amount = query.iter().count();
+    for (pole, mut inertia, mut stiffness) in query.iter_mut() {
+        let (inertia_factor, stiffness_factor) = profile(&settings, pole.index, amount);
+        inertia.0 = settings.moment_of_inertia * inertia_factor;
+        stiffness.0 = settings.stiffness * stiffness_factor;
+    }
+}
+
+pub struct MediumPlugin;
+
+impl Plugin for MediumPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update.before("physics"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Normal modes of a uniform chain
+//! Discrete counterparts of sine/cosine standing waves, depending on which ends are anchored
+
+use std::f32::consts::PI;
+
+use crate::{
+    poles::pole_distance,
+    settings::{Model, SoftSettings},
+};
+
+/// Which ends of the chain are anchored
+#[derive(Clone, Copy)]
+pub struct Anchors {
+    pub bottom: bool,
+    pub top: bool,
+}
+
+impl Anchors {
+    /// Anchored ends of the chain with given settings
+    pub fn new(settings: &SoftSettings) -> Self {
+        Self {
+            bottom: settings.bottom_boundary.anchored(),
+            top: settings.top_boundary.anchored(),
+        }
+    }
+}
+
+/// Phase difference between neighbouring poles in mode `n` of a chain of `amount` poles
+/// Modes are counted from 1, for chains with both ends loose mode 1 is the rigid rotation
+pub fn wavenumber(n: u32, amount: u32, anchors: Anchors) -> f32 {
+    let (n, amount) = (n as f32, amount as f32);
+    match (anchors.bottom, anchors.top) {
+        (true, true) => n * PI / (amount + 1.0),
+        (false, false) => (n - 1.0) * PI / amount,
+        _ => (n - 0.5) * PI / (amount + 0.5),
+    }
+}
+
+/// Shape of mode `n` at pole `index` of a chain of `amount` poles, with amplitude 1
+pub fn shape(n: u32, index: usize, amount: u32, anchors: Anchors) -> f32 {
+    let q = wavenumber(n, amount, anchors);
+    let i = index as f32;
+    match (anchors.bottom, anchors.top) {
+        (true, _) => (q * (i + 1.0)).sin(),
+        (false, _) => (q * (i + 0.5)).cos(),
+    }
+}
+
+/// Angular frequency of mode `n` of a uniform chain of `amount` poles with given settings
+/// Restoring torque of the 2-string model and linearised sine-Gordon gravity lift all modes above the cutoff
+pub fn angular_frequency(n: u32, amount: u32, soft: &SoftSettings) -> f32 {
+    let q = wavenumber(n, amount, Anchors::new(soft));
+    let speed = (soft.stiffness / soft.moment_of_inertia).sqrt();
+    let coupling = 2.0 * speed / pole_distance(amount) * (q / 2.0).sin();
+    let restoring = match soft.model {
+        Model::SingleString => 0.0,
+        Model::TwoString => soft.string_torsion,
+        Model::SineGordon => soft.gravity,
+    } / soft.moment_of_inertia;
+    (coupling * coupling + restoring).sqrt()
+}
+
+/// Amplitude of mode `n` in the given state of the chain
+/// Velocities are included, so the amplitude of a freely oscillating mode stays constant
+pub fn amplitude(n: u32, angles: &[f32], velocities: &[f32], anchors: Anchors, omega: f32) -> f32 {
+    let amount = angles.len() as u32;
+    let (mut angle, mut velocity, mut norm) = (0.0, 0.0, 0.0);
+    for (i, (a, v)) in angles.iter().zip(velocities).enumerate() {
+        let shape = shape(n, i, amount, anchors);
+        angle += a * shape;
+        velocity += v * shape;
+        norm += shape * shape;
+    }
+    let (angle, velocity) = (angle / norm, velocity / norm);
+    match omega > 0.0 {
+        true => angle.hypot(velocity / omega),
+        false => angle.abs(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::TAU;
+
+    use super::*;
+    use crate::{
+        chain::TorsionChain,
+        settings::{Boundary, HardSettings, Integrator},
+    };
+
+    fn settings(bottom_boundary: Boundary, top_boundary: Boundary, model: Model) -> SoftSettings {
+        SoftSettings {
+            moment_of_inertia: 0.25,
+            model,
+            string_torsion: 0.5,
+            damping: 0.0,
+            sponge_damping: 0.0,
+            bottom_boundary,
+            top_boundary,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn low_modes_match_continuous_string() {
+        let amount = 200;
+        let distance = pole_distance(amount);
+        // Wave speed `sqrt(k / I)`
+        let speed = 2.0;
+        // Ends, model, length of the continuous string beyond the end poles in spacings,
+        // lag of the mode number behind the amount of half-waves, square of the cutoff frequency
+        let cases = [
+            (
+                Boundary::Fixed,
+                Boundary::Fixed,
+                Model::SingleString,
+                1.0,
+                0.0,
+                0.0,
+            ),
+            (
+                Boundary::Free,
+                Boundary::Free,
+                Model::SingleString,
+                0.0,
+                1.0,
+                0.0,
+            ),
+            (
+                Boundary::Fixed,
+                Boundary::Free,
+                Model::SingleString,
+                0.5,
+                0.5,
+                0.0,
+            ),
+            (
+                Boundary::Fixed,
+                Boundary::Fixed,
+                Model::TwoString,
+                1.0,
+                0.0,
+                0.5 / 0.25,
+            ),
+        ];
+        for (bottom, top, model, extra, lag, cutoff) in cases {
+            let soft = settings(bottom, top, model);
+            let length = (amount as f32 + extra) * distance;
+            for n in 1..=5 {
+                let wave = (n as f32 - lag) * PI * speed / length;
+                let expected = (wave * wave + cutoff).sqrt();
+                let omega = angular_frequency(n, amount, &soft);
+                assert!(
+                    (omega - expected).abs() <= 1e-3 * expected.max(1.0),
+                    "{} to {}, {}: mode {} at {}, expected {}",
+                    bottom.name(),
+                    top.name(),
+                    model.name(),
+                    n,
+                    omega,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mode_returns_after_period() {
+        let amount = 24;
+        let hard = HardSettings {
+            distance: pole_distance(amount),
+            ..Default::default()
+        };
+        for (bottom, top, model) in [
+            (Boundary::Fixed, Boundary::Fixed, Model::SingleString),
+            (Boundary::Free, Boundary::Free, Model::SingleString),
+            (Boundary::Free, Boundary::Fixed, Model::TwoString),
+        ] {
+            let soft = SoftSettings {
+                integrator: Integrator::RungeKutta4,
+                ..settings(bottom, top, model)
+            };
+            let anchors = Anchors::new(&soft);
+            let n = 3;
+            let mut chain = TorsionChain::new(amount as usize, &soft, &hard);
+            for (i, angle) in chain.angles.iter_mut().enumerate() {
+                *angle = 0.1 * shape(n, i, amount, anchors);
+            }
+            let start = chain.angles.clone();
+            let omega = angular_frequency(n, amount, &soft);
+            let period = TAU / omega;
+            let steps = 2000;
+            for _ in 0..steps {
+                chain.step(period / steps as f32);
+            }
+            // Velocities are sensitive to an error in the period, angles only to its square
+            let error = chain.angles.iter().zip(&start).zip(&chain.velocities).fold(
+                0.0f32,
+                |max, ((angle, expected), velocity)| {
+                    max.max((angle - expected).abs())
+                        .max((velocity / omega).abs())
+                },
+            );
+            assert!(
+                error < 1e-4,
+                "{} to {}, {}: error {}",
+                bottom.name(),
+                top.name(),
+                model.name(),
+                error
+            );
+        }
+    }
+}
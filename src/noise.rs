@@ -0,0 +1,15 @@
+//! Deterministic pseudo-random numbers
+//! The same seed and index always give the same value, which keeps runs reproducible
+
+/// SplitMix64 finalizer over seed and index
+fn hash(seed: u64, index: u64) -> u64 {
+    let mut z = seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Uniformly distributed value in `[-1; 1]`
+pub fn uniform(seed: u64, index: u64) -> f32 {
+    (hash(seed, index) >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+}
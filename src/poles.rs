@@ -1,25 +1,35 @@
 //! Pole spawning and despawning
+//! Meshes are attached separately, so that the chain can run without rendering
 
 use bevy::{ecs::schedule::ShouldRun, prelude::*};
-use itertools::Itertools;
 
 use crate::{
-    settings::{HardReset, HardSettings},
-    wave::AngularVelocity,
+    chain::sponge_profile,
+    initial::initial_state,
+    medium::{Inertia, Stiffness},
+    scaled_time::ScaledTime,
+    settings::{HardReset, HardSettings, SoftSettings},
+    wave::{Angle, AngularVelocity},
 };
 
 const TOTAL_HEIGHT: f32 = 10.;
-
-#[derive(Clone, Copy)]
-pub enum Neighbour {
-    Empty,
-    Pole(Entity),
-}
+const COLOR: Color = Color::rgb(1.0, 0.718, 0.169);
+const SPONGE_COLOR: Color = Color::rgb(0.29, 0.435, 0.647);
 
 #[derive(Component)]
 pub struct Pole {
-    pub below: Neighbour,
-    pub above: Neighbour,
+    /// Position in the chain, counting from the bottom
+    pub index: usize,
+}
+
+/// Size of the pole's cross-section, poles are spaced by 1.5 of it
+pub fn pole_side(amount: u32) -> f32 {
+    TOTAL_HEIGHT / (amount as f32 + (amount as f32 - 1.) / 2.)
+}
+
+/// Distance between the centers of neighbouring poles
+pub fn pole_distance(amount: u32) -> f32 {
+    pole_side(amount) * 1.5
 }
 
 pub fn despawn(mut commands: Commands, query: Query<Entity, With<Pole>>) {
@@ -30,51 +40,83 @@ pub fn despawn(mut commands: Commands, query: Query<Entity, With<Pole>>) {
 
 pub fn spawn(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
     mut settings: ResMut<HardSettings>,
+    soft_settings: Res<SoftSettings>,
     mut hard_reset: ResMut<HardReset>,
+    mut scaled_time: ResMut<ScaledTime>,
 ) {
-    let side = TOTAL_HEIGHT / (settings.amount as f32 + (settings.amount as f32 - 1.) / 2.);
-    settings.distance = side * 1.5;
+    let side = pole_side(settings.amount);
+    settings.distance = pole_distance(settings.amount);
+
+    for i in 0..settings.amount {
+        let y = i as f32 * settings.distance + (side - TOTAL_HEIGHT) / 2.;
+        let (angle, angular_velocity) = initial_state(&settings, &soft_settings, i as usize);
+        commands
+            .spawn_bundle(TransformBundle::from_transform(
+                Transform::from_translation(Vec3::new(0., y, 0.))
+                    .with_rotation(Quat::from_rotation_y(angle)),
+            ))
+            .insert(Pole { index: i as usize })
+            .insert(Angle(angle))
+            .insert(AngularVelocity(angular_velocity))
+            .insert(Inertia(soft_settings.moment_of_inertia))
+            .insert(Stiffness(soft_settings.stiffness));
+    }
+
+    scaled_time.reset();
+    hard_reset.0 = false;
+}
+
+/// Gives new poles their meshes and materials
+fn attach_meshes(
+    mut commands: Commands,
+    query: Query<Entity, Added<Pole>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings: Res<HardSettings>,
+) {
+    if query.is_empty() {
+        return;
+    }
+    let side = pole_side(settings.amount);
     let length = settings.length * side;
     let mesh_handle = meshes.add(shape::Box::new(length, side, side).into());
-    let material_handle = materials.add(Color::rgb_u8(0xFF, 0xB7, 0x2B).into());
-
-    let commands = &mut commands;
-
-    #[allow(clippy::needless_collect)]
-    let poles = [Neighbour::Empty]
-        .into_iter()
-        .chain((0..settings.amount).map(|i| {
-            let y = i as f32 * side * 1.5 + (side - TOTAL_HEIGHT) / 2.;
-            let id = commands
-                .spawn_bundle(PbrBundle {
-                    mesh: mesh_handle.clone(),
-                    material: material_handle.clone(),
-                    transform: Transform::from_translation(Vec3::new(0., y, 0.)),
-                    ..Default::default()
-                })
-                .id();
-            Neighbour::Pole(id)
-        }))
-        .chain([Neighbour::Empty].into_iter())
-        .collect::<Vec<_>>();
-
-    poles
-        .into_iter()
-        .tuple_windows::<(_, _, _)>()
-        .for_each(|(below, current, above)| {
-            if let Neighbour::Pole(entity) = current {
-                let pole = Pole { below, above };
-                commands
-                    .entity(entity)
-                    .insert(pole)
-                    .insert(AngularVelocity(0.));
-            }
-        });
+    for entity in query.iter() {
+        commands
+            .entity(entity)
+            .insert(mesh_handle.clone())
+            .insert(materials.add(COLOR.into()))
+            .insert(Visibility::default())
+            .insert(ComputedVisibility::default());
+    }
+}
 
-    hard_reset.0 = false;
+/// Tints poles inside sponge layers, proportionally to their damping
+/// Only redone when the thickness of the layers changes or poles get new materials
+fn tint(
+    query: Query<(&Pole, &Handle<StandardMaterial>)>,
+    added: Query<(), (With<Pole>, Added<Handle<StandardMaterial>>)>,
+    settings: Res<SoftSettings>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut last_sponges: Local<Option<(u32, u32)>>,
+) {
+    let sponges = Some((settings.bottom_sponge, settings.top_sponge));
+    if *last_sponges == sponges && added.is_empty() {
+        return;
+    }
+    *last_sponges = sponges;
+    let amount = query.iter().count();
+    let mix = |a: f32, b: f32, t: f32| a + (b - a) * t;
+    for (pole, handle) in query.iter() {
+        let t = sponge_profile(&settings, pole.index, amount);
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = Color::rgb(
+                mix(COLOR.r(), SPONGE_COLOR.r(), t),
+                mix(COLOR.g(), SPONGE_COLOR.g(), t),
+                mix(COLOR.b(), SPONGE_COLOR.b(), t),
+            );
+        }
+    }
 }
 
 fn hard_reset(hard_reset: Res<HardReset>) -> ShouldRun {
@@ -94,3 +136,12 @@ impl Plugin for PolePlugin {
         );
     }
 }
+
+/// Renders the poles
+pub struct PoleMeshPlugin;
+
+impl Plugin for PoleMeshPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(attach_meshes).add_system(tint);
+    }
+}
@@ -0,0 +1,159 @@
+//! Settings presets, built-in or stored on disk as RON
+
+use std::{fs, path::Path};
+
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    drive::Drive,
+    modes,
+    settings::{Boundary, HardSettings, InitialState, Medium, SoftSettings},
+};
+
+/// Reads a RON file
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    ron::from_str(&text).map_err(|e| e.to_string())
+}
+
+/// Writes a RON file
+pub fn save_ron<T: Serialize>(value: &T, path: impl AsRef<Path>) -> Result<(), String> {
+    let text =
+        ron::ser::to_string_pretty(value, PrettyConfig::default()).map_err(|e| e.to_string())?;
+    fs::write(path, text).map_err(|e| e.to_string())
+}
+
+/// Complete set of settings
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub soft: SoftSettings,
+    pub hard: HardSettings,
+}
+
+impl Preset {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        load_ron(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        save_ron(self, path)
+    }
+
+    /// Built-in preset of the given key, or a preset file at the given path
+    pub fn find(name: &str) -> Result<Self, String> {
+        match BuiltIn::ALL.iter().find(|preset| preset.key() == name) {
+            Some(preset) => Ok(preset.preset()),
+            None if Path::new(name).exists() => Self::load(name),
+            None => {
+                let keys = BuiltIn::ALL.map(|preset| preset.key()).join(", ");
+                Err(format!(
+                    "No preset `{}`, expected one of: {}, or a path to a preset file",
+                    name, keys
+                ))
+            }
+        }
+    }
+}
+
+/// Presets shipped with the application
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BuiltIn {
+    Default,
+    /// Third normal mode between fixed ends
+    StandingWave,
+    /// Top end pushed at the frequency of the first mode
+    Resonance,
+    /// Gaussian pulse in a lossy medium
+    DampedPulse,
+    /// Sine wave leaving through an absorbing end
+    TravellingWave,
+    /// Pulse partially reflected from a heavier half of the chain
+    ImpedanceStep,
+}
+
+impl BuiltIn {
+    pub const ALL: [Self; 6] = [
+        Self::Default,
+        Self::StandingWave,
+        Self::Resonance,
+        Self::DampedPulse,
+        Self::TravellingWave,
+        Self::ImpedanceStep,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::StandingWave => "Standing wave",
+            Self::Resonance => "Resonance",
+            Self::DampedPulse => "Damped pulse",
+            Self::TravellingWave => "Travelling wave",
+            Self::ImpedanceStep => "Impedance step",
+        }
+    }
+
+    /// Name used on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::StandingWave => "standing-wave",
+            Self::Resonance => "resonance",
+            Self::DampedPulse => "damped-pulse",
+            Self::TravellingWave => "travelling-wave",
+            Self::ImpedanceStep => "impedance-step",
+        }
+    }
+
+    pub fn preset(&self) -> Preset {
+        let mut preset = Preset::default();
+        let (soft, hard) = (&mut preset.soft, &mut preset.hard);
+        match self {
+            Self::Default => {}
+            Self::StandingWave => {
+                soft.bottom_boundary = Boundary::Fixed;
+                soft.top_boundary = Boundary::Fixed;
+                soft.damping = 0.0;
+                hard.initial_state = InitialState::StandingWave;
+                hard.initial_mode = 3;
+                hard.initial_amplitude = 0.5;
+            }
+            Self::Resonance => {
+                soft.bottom_boundary = Boundary::Fixed;
+                soft.damping = -0.0005;
+                let omega = modes::angular_frequency(1, hard.amount, soft);
+                soft.top_drive = Drive::Sine {
+                    frequency: omega / std::f32::consts::TAU,
+                    phase: 0.0,
+                    amplitude: 0.01,
+                };
+            }
+            Self::DampedPulse => {
+                soft.damping = -0.02;
+                hard.initial_state = InitialState::Pulse;
+            }
+            Self::TravellingWave => {
+                soft.bottom_boundary = Boundary::Driven;
+                soft.top_boundary = Boundary::Absorbing;
+                soft.bottom_drive = Drive::Sine {
+                    frequency: 0.5,
+                    phase: 0.0,
+                    amplitude: 0.5,
+                };
+            }
+            Self::ImpedanceStep => {
+                soft.medium = Medium::Step;
+                soft.inertia_ratio = 4.0;
+                soft.bottom_boundary = Boundary::Driven;
+                soft.top_boundary = Boundary::Absorbing;
+                soft.bottom_drive = Drive::Pulse {
+                    delay: 1.0,
+                    width: 0.1,
+                    amplitude: 1.0,
+                };
+            }
+        }
+        preset
+    }
+}
@@ -0,0 +1,126 @@
+//! Recording the state of the chain for analysis in external tools
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+
+use crate::chain::TorsionChain;
+
+/// State of the chain at some moment
+struct Sample {
+    time: f64,
+    angles: Vec<f32>,
+    velocities: Vec<f32>,
+}
+
+/// Samples of the chain taken during a recording
+pub struct Recorder {
+    pub recording: bool,
+    /// Time between samples, 0 samples every physics step
+    pub interval: f32,
+    samples: Vec<Sample>,
+    /// Time of the next sample, `None` at the start of a recording
+    next_sample: Option<f64>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            recording: false,
+            interval: 0.05,
+            samples: Vec::new(),
+            next_sample: None,
+        }
+    }
+}
+
+impl Recorder {
+    /// Discards previous samples and starts recording
+    pub fn start(&mut self) {
+        self.samples.clear();
+        self.next_sample = None;
+        self.recording = true;
+    }
+
+    pub fn stop(&mut self) {
+        self.recording = false;
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Writes the samples as CSV, one row per sample
+    pub fn export_csv(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        self.write_csv(&mut BufWriter::new(file))
+            .map_err(|e| e.to_string())
+    }
+
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        let amount = self.samples.first().map_or(0, |sample| sample.angles.len());
+        write!(out, "time")?;
+        for i in 0..amount {
+            write!(out, ",angle_{}", i)?;
+        }
+        for i in 0..amount {
+            write!(out, ",velocity_{}", i)?;
+        }
+        writeln!(out)?;
+        for sample in &self.samples {
+            write!(out, "{}", sample.time)?;
+            for value in sample.angles.iter().chain(&sample.velocities) {
+                write!(out, ",{}", value)?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+}
+
+/// Samples the chain after each physics step
+fn record(mut recorder: ResMut<Recorder>, chain: Res<TorsionChain>) {
+    if !recorder.recording {
+        return;
+    }
+    if let Some(next_sample) = recorder.next_sample {
+        // Time jumps backwards after rewinding or loading a snapshot
+        let rewound = next_sample - chain.time > recorder.interval as f64;
+        if chain.time < next_sample - 1e-6 && !rewound {
+            return;
+        }
+    }
+    // Restarts the recording when the chain was reset
+    if let Some(sample) = recorder.samples.last() {
+        if sample.angles.len() != chain.angles.len() {
+            recorder.start();
+        }
+    }
+    recorder.samples.push(Sample {
+        time: chain.time,
+        angles: chain.angles.clone(),
+        velocities: chain.velocities.clone(),
+    });
+    let start = match recorder.next_sample {
+        Some(next_sample) if next_sample <= chain.time + 1e-6 => next_sample,
+        _ => chain.time,
+    };
+    recorder.next_sample = Some(start + recorder.interval as f64);
+}
+
+pub struct RecorderPlugin;
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recorder>()
+            .add_system(record.with_run_criteria("physics-tick").after("step-chain"));
+    }
+}
@@ -1,12 +1,42 @@
 //! Global time and delta time, with consideration to time scale
-use bevy::prelude::*;
+//! Physics advances in fixed steps, accumulated from scaled frame time
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
 
 use crate::settings::SoftSettings;
 
 #[derive(Default)]
 pub struct ScaledTime {
+    /// Length of a single physics step
     pub delta: f32,
     pub total: f64,
+    /// Scaled time not yet simulated
+    accumulator: f64,
+    /// Physics steps executed this frame
+    substeps: u32,
+    /// Whether the last frame needed more steps than allowed
+    pub falling_behind: bool,
+    /// Stops accumulating time, physics only advances by explicit steps
+    pub paused: bool,
+    /// Physics steps requested while paused
+    pending_ticks: u32,
+}
+
+impl ScaledTime {
+    /// Requests a number of physics steps, executed even when paused
+    pub fn step(&mut self, ticks: u32) {
+        self.pending_ticks += ticks;
+    }
+
+    /// Starts the time over, drives of a reset chain begin from the start
+    pub fn reset(&mut self) {
+        self.total = 0.0;
+        self.accumulator = 0.0;
+    }
+
+    /// Whether physics advanced during this frame
+    pub fn ticked(&self) -> bool {
+        self.substeps > 0
+    }
 }
 
 pub struct ScaledTimePlugin;
@@ -19,6 +49,41 @@ impl Plugin for ScaledTimePlugin {
 }
 
 fn update(mut scaled_time: ResMut<ScaledTime>, time: Res<Time>, settings: Res<SoftSettings>) {
-    scaled_time.delta = (time.delta_seconds() * settings.time_scale).clamp(0.0, 0.01);
-    scaled_time.total += scaled_time.delta as f64;
+    scaled_time.delta = settings.timestep;
+    if !scaled_time.paused {
+        scaled_time.accumulator += (time.delta_seconds() * settings.time_scale) as f64;
+    }
+    scaled_time.substeps = 0;
+}
+
+/// Substeps allowed in a frame, fast-forwarding takes proportionally more of them
+fn substep_limit(settings: &SoftSettings) -> u32 {
+    (settings.max_substeps as f32 * settings.time_scale.max(1.0)).ceil() as u32
+}
+
+/// Runs physics once per fixed step in the accumulated time
+/// When the substep limit is reached, the remaining time is dropped
+/// Explicitly requested steps go first and are spread over frames by the same limit
+pub fn physics_tick(mut scaled_time: ResMut<ScaledTime>, settings: Res<SoftSettings>) -> ShouldRun {
+    let delta = scaled_time.delta as f64;
+    let limit = substep_limit(&settings);
+    if scaled_time.pending_ticks > 0 && scaled_time.substeps < limit {
+        scaled_time.pending_ticks -= 1;
+        scaled_time.total += delta;
+        scaled_time.substeps += 1;
+        return ShouldRun::YesAndCheckAgain;
+    }
+    if scaled_time.accumulator < delta {
+        scaled_time.falling_behind = false;
+        return ShouldRun::No;
+    }
+    if scaled_time.substeps >= limit {
+        scaled_time.accumulator = 0.0;
+        scaled_time.falling_behind = true;
+        return ShouldRun::No;
+    }
+    scaled_time.accumulator -= delta;
+    scaled_time.total += delta;
+    scaled_time.substeps += 1;
+    ShouldRun::YesAndCheckAgain
 }
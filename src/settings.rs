@@ -1,23 +1,200 @@
 //! Setting objects
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::drive::Drive;
+
+/// Numerical scheme used to advance the simulation
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Integrator {
+    SymplecticEuler,
+    VelocityVerlet,
+    RungeKutta4,
+    CrankNicolson,
+}
+
+impl Integrator {
+    pub const ALL: [Self; 4] = [
+        Self::SymplecticEuler,
+        Self::VelocityVerlet,
+        Self::RungeKutta4,
+        Self::CrankNicolson,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SymplecticEuler => "Symplectic Euler",
+            Self::VelocityVerlet => "Velocity Verlet",
+            Self::RungeKutta4 => "Runge-Kutta 4",
+            Self::CrankNicolson => "Crank-Nicolson (implicit)",
+        }
+    }
+}
+
+/// Physical model of the medium
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Model {
+    /// Poles are only coupled with each other
+    SingleString,
+    /// Poles are additionally held by two strings, which return them to the rest angle
+    TwoString,
+    /// Poles feel a gravity-like torque, which supports kink and breather solitons
+    SineGordon,
+}
+
+impl Model {
+    pub const ALL: [Self; 3] = [Self::SingleString, Self::TwoString, Self::SineGordon];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SingleString => "1-string",
+            Self::TwoString => "2-string",
+            Self::SineGordon => "Sine-Gordon",
+        }
+    }
+}
+
+/// Distribution of inertia and stiffness along the chain
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Medium {
+    Uniform,
+    /// Upper half of the chain has different properties
+    Step,
+    /// Properties change linearly from the bottom to the top
+    Gradient,
+    /// Properties vary randomly around the base value
+    Disorder,
+}
+
+impl Medium {
+    pub const ALL: [Self; 4] = [Self::Uniform, Self::Step, Self::Gradient, Self::Disorder];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Uniform => "Uniform",
+            Self::Step => "Step change halfway",
+            Self::Gradient => "Linear gradient",
+            Self::Disorder => "Random disorder",
+        }
+    }
+}
+
+/// Condition on an end of the chain
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Boundary {
+    /// End pole is loose, driven by torque
+    Free,
+    /// End pole is anchored at angle 0, driven by torque
+    Fixed,
+    /// End pole is anchored, the anchor angle follows the drive
+    Driven,
+    /// End pole is loose and damped, so that waves leave without reflecting, driven by torque
+    Absorbing,
+}
+
+impl Boundary {
+    pub const ALL: [Self; 4] = [Self::Free, Self::Fixed, Self::Driven, Self::Absorbing];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Free => "Free",
+            Self::Fixed => "Fixed",
+            Self::Driven => "Driven angle",
+            Self::Absorbing => "Absorbing",
+        }
+    }
+
+    /// Whether the end pole is linked to an anchor
+    pub fn anchored(&self) -> bool {
+        matches!(self, Self::Fixed | Self::Driven)
+    }
+}
+
+/// Kind of losses in the medium
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DampingModel {
+    /// Proportional to angular velocity
+    Viscous,
+    /// Constant magnitude, opposing the motion
+    Coulomb,
+    /// Proportional to the square of angular velocity (air drag)
+    Quadratic,
+    /// Proportional to the rate of change of twist between neighbours
+    KelvinVoigt,
+}
+
+impl DampingModel {
+    pub const ALL: [Self; 4] = [
+        Self::Viscous,
+        Self::Coulomb,
+        Self::Quadratic,
+        Self::KelvinVoigt,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Viscous => "Viscous",
+            Self::Coulomb => "Coulomb friction",
+            Self::Quadratic => "Quadratic drag",
+            Self::KelvinVoigt => "Internal (Kelvin-Voigt)",
+        }
+    }
+
+    /// Unit of the damping coefficient
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Viscous => " N * s",
+            Self::Coulomb => " N",
+            Self::Quadratic => " N * s^2",
+            Self::KelvinVoigt => " N * m^2 * s",
+        }
+    }
+}
 
 /// Settings that don't require restart
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SoftSettings {
+    /// Torsional rigidity of the medium
     pub stiffness: f32,
+    /// Moment of inertia per unit length
     pub moment_of_inertia: f32,
+    pub medium: Medium,
+    /// Inertia at the top relative to the bottom (step and gradient media)
+    pub inertia_ratio: f32,
+    /// Stiffness at the top relative to the bottom (step and gradient media)
+    pub stiffness_ratio: f32,
+    /// Relative strength of random variations (disordered medium)
+    pub disorder: f32,
+    pub disorder_seed: u32,
+    pub model: Model,
+    /// Torsion constant of the supporting strings per unit length (2-string model)
+    pub string_torsion: f32,
+    /// Strength of the gravity-like torque per unit length (sine-Gordon model)
+    pub gravity: f32,
     pub time_scale: f32,
+    /// Length of a single physics step
+    pub timestep: f32,
+    /// Limit of physics steps per frame at real time speed
+    pub max_substeps: u32,
+    /// Physics steps taken at once when stepping a paused simulation
+    pub step_ticks: u32,
+    pub integrator: Integrator,
+    pub damping_model: DampingModel,
+    /// Damping coefficient per unit length (for internal damping: of the whole medium)
     pub damping: f32,
-    pub anchor_top: bool,
-    pub anchor_bottom: bool,
+    pub top_boundary: Boundary,
+    pub bottom_boundary: Boundary,
 
-    pub top_frequency: f32,
-    pub top_phase: f32,
-    pub top_force: f32,
+    pub top_drive: Drive,
+    pub bottom_drive: Drive,
 
-    pub bottom_frequency: f32,
-    pub bottom_phase: f32,
-    pub bottom_force: f32,
+    /// Thickness of the sponge layers in poles
+    pub top_sponge: u32,
+    pub bottom_sponge: u32,
+    /// Damping coefficient per unit length at the very ends of sponge layers
+    pub sponge_damping: f32,
 }
 
 impl Default for SoftSettings {
@@ -25,30 +202,94 @@ impl Default for SoftSettings {
         Self {
             stiffness: 1.0,
             moment_of_inertia: 0.01,
+            medium: Medium::Uniform,
+            inertia_ratio: 4.0,
+            stiffness_ratio: 1.0,
+            disorder: 0.5,
+            disorder_seed: 0,
+            model: Model::SingleString,
+            string_torsion: 0.1,
+            gravity: 0.1,
             time_scale: 1.0,
+            timestep: 0.005,
+            max_substeps: 16,
+            step_ticks: 10,
+            integrator: Integrator::SymplecticEuler,
+            damping_model: DampingModel::Viscous,
             damping: -0.01,
-            anchor_bottom: false,
-            anchor_top: false,
+            bottom_boundary: Boundary::Free,
+            top_boundary: Boundary::Free,
+
+            top_drive: Drive::default(),
+            bottom_drive: Drive::default(),
+
+            top_sponge: 0,
+            bottom_sponge: 0,
+            sponge_damping: 0.5,
+        }
+    }
+}
+
+/// State of the chain after reset
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InitialState {
+    Rest,
+    /// Gaussian twist at rest, splits into two pulses
+    Pulse,
+    /// Normal mode of the chain
+    StandingWave,
+    /// Gaussian envelope over a carrier wave, travelling upwards
+    Packet,
+    /// Random twist of every pole
+    Noise,
+    /// Smoothed step, one part of the chain is twisted
+    Twist,
+}
 
-            top_frequency: 0.0,
-            top_phase: 0.0,
-            top_force: 0.0,
+impl InitialState {
+    pub const ALL: [Self; 6] = [
+        Self::Rest,
+        Self::Pulse,
+        Self::StandingWave,
+        Self::Packet,
+        Self::Noise,
+        Self::Twist,
+    ];
 
-            bottom_frequency: 0.0,
-            bottom_phase: 0.0,
-            bottom_force: 0.0,
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rest => "Rest",
+            Self::Pulse => "Gaussian pulse",
+            Self::StandingWave => "Standing wave",
+            Self::Packet => "Travelling wave packet",
+            Self::Noise => "Random noise",
+            Self::Twist => "Step twist",
         }
     }
 }
 
 /// Settings that require restart
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct HardSettings {
     /// Amount of the poles
     pub amount: u32,
     /// Length of the poles
     pub length: f32,
     /// Distance between poles (derived)
+    #[serde(skip)]
     pub distance: f32,
+
+    pub initial_state: InitialState,
+    /// Peak angle of the initial state
+    pub initial_amplitude: f32,
+    /// Width of pulses, packets and steps
+    pub initial_width: f32,
+    /// Center of pulses, packets and steps, as a fraction of the chain from the bottom
+    pub initial_position: f32,
+    /// Mode of standing waves, or wavelengths per chain of packets
+    pub initial_mode: u32,
+    pub initial_seed: u32,
 }
 
 impl Default for HardSettings {
@@ -57,6 +298,13 @@ impl Default for HardSettings {
             amount: 32,
             length: 5.0,
             distance: 0.0,
+
+            initial_state: InitialState::Rest,
+            initial_amplitude: 1.0,
+            initial_width: 0.5,
+            initial_position: 0.5,
+            initial_mode: 1,
+            initial_seed: 0,
         }
     }
 }
@@ -0,0 +1,192 @@
+//! Saving and restoring the complete state of the simulation
+
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    poles::Pole,
+    presets::{load_ron, save_ron, Preset},
+    scaled_time::ScaledTime,
+    settings::{HardReset, HardSettings, SoftSettings},
+    wave::{Angle, AngularVelocity},
+};
+
+/// Settings and state of every pole at some moment
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub preset: Preset,
+    pub time: f64,
+    /// Angles of the poles, from the bottom
+    pub angles: Vec<f32>,
+    pub velocities: Vec<f32>,
+}
+
+impl Snapshot {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let snapshot: Self = load_ron(path)?;
+        let amount = snapshot.preset.hard.amount as usize;
+        if snapshot.angles.len() != amount || snapshot.velocities.len() != amount {
+            return Err(format!("Snapshot does not contain {} poles", amount));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        save_ron(self, path)
+    }
+}
+
+pub enum SnapshotEvent {
+    Save(PathBuf),
+    Load(PathBuf),
+}
+
+/// Error of the last snapshot operation
+#[derive(Default)]
+pub struct SnapshotStatus(pub Option<String>);
+
+/// Snapshot waiting for the chain to be respawned
+#[derive(Default)]
+pub struct PendingSnapshot(pub Option<Snapshot>);
+
+fn save(
+    mut events: EventReader<SnapshotEvent>,
+    mut status: ResMut<SnapshotStatus>,
+    soft_settings: Res<SoftSettings>,
+    hard_settings: Res<HardSettings>,
+    scaled_time: Res<ScaledTime>,
+    query: Query<(&Pole, &Angle, &AngularVelocity)>,
+) {
+    for event in events.iter() {
+        if let SnapshotEvent::Save(path) = event {
+            let amount = query.iter().count();
+            let mut snapshot = Snapshot {
+                preset: Preset {
+                    soft: soft_settings.clone(),
+                    hard: hard_settings.clone(),
+                },
+                time: scaled_time.total,
+                angles: vec![0.0; amount],
+                velocities: vec![0.0; amount],
+            };
+            for (pole, angle, angular_velocity) in query.iter() {
+                snapshot.angles[pole.index] = angle.0;
+                snapshot.velocities[pole.index] = angular_velocity.0;
+            }
+            status.0 = snapshot.save(path).err();
+        }
+    }
+}
+
+/// Applies the settings of a loaded snapshot and respawns the chain, the state is restored afterwards
+fn load(
+    mut events: EventReader<SnapshotEvent>,
+    mut status: ResMut<SnapshotStatus>,
+    mut pending: ResMut<PendingSnapshot>,
+    mut soft_settings: ResMut<SoftSettings>,
+    mut hard_settings: ResMut<HardSettings>,
+    mut hard_reset: ResMut<HardReset>,
+) {
+    for event in events.iter() {
+        if let SnapshotEvent::Load(path) = event {
+            let result = Snapshot::load(path).map(|snapshot| {
+                *soft_settings = snapshot.preset.soft.clone();
+                *hard_settings = snapshot.preset.hard.clone();
+                hard_reset.0 = true;
+                pending.0 = Some(snapshot);
+            });
+            status.0 = result.err();
+        }
+    }
+}
+
+/// Overwrites the state of respawned poles with the pending snapshot
+fn restore(
+    mut pending: ResMut<PendingSnapshot>,
+    hard_reset: Res<HardReset>,
+    mut scaled_time: ResMut<ScaledTime>,
+    mut query: Query<(&Pole, &mut Angle, &mut AngularVelocity)>,
+) {
+    let ready = match &pending.0 {
+        Some(snapshot) => !hard_reset.0 && query.iter().count() == snapshot.angles.len(),
+        None => false,
+    };
+    if !ready {
+        return;
+    }
+    let snapshot = pending.0.take().unwrap();
+    scaled_time.total = snapshot.time;
+    for (pole, mut angle, mut angular_velocity) in query.iter_mut() {
+        angle.0 = snapshot.angles[pole.index];
+        angular_velocity.0 = snapshot.velocities[pole.index];
+    }
+}
+
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SnapshotEvent>()
+            .init_resource::<SnapshotStatus>()
+            .init_resource::<PendingSnapshot>()
+            .add_system(save.before("physics"))
+            .add_system(load.before("physics"))
+            .add_system(restore.after("clear-history").before("physics"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::Events;
+
+    use super::*;
+    use crate::{headless, presets::BuiltIn};
+
+    /// Time, angles and velocities of the poles, from the bottom
+    fn state(app: &mut App) -> (f64, Vec<f32>, Vec<f32>) {
+        let time = app.world.resource::<ScaledTime>().total;
+        let mut poles = app
+            .world
+            .query::<(&Pole, &Angle, &AngularVelocity)>()
+            .iter(&app.world)
+            .map(|(pole, angle, angular_velocity)| (pole.index, angle.0, angular_velocity.0))
+            .collect::<Vec<_>>();
+        poles.sort_unstable_by_key(|(index, _, _)| *index);
+        let angles = poles.iter().map(|(_, angle, _)| *angle).collect();
+        let velocities = poles.iter().map(|(_, _, velocity)| *velocity).collect();
+        (time, angles, velocities)
+    }
+
+    fn step(app: &mut App, steps: u32) {
+        for _ in 0..steps {
+            app.world.resource_mut::<ScaledTime>().step(1);
+            app.update();
+        }
+    }
+
+    #[test]
+    fn restored_chain_continues_identically() {
+        let path = std::env::temp_dir().join("torsion-waves-snapshot-test.ron");
+        let mut original = headless::app(BuiltIn::Resonance.preset(), None);
+        original.update();
+        step(&mut original, 300);
+        original
+            .world
+            .resource_mut::<Events<SnapshotEvent>>()
+            .send(SnapshotEvent::Save(path.clone()));
+        original.update();
+        assert_eq!(original.world.resource::<SnapshotStatus>().0, None);
+
+        let snapshot = Snapshot::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let mut restored = headless::app(snapshot.preset.clone(), Some(snapshot));
+        restored.update();
+        assert_eq!(state(&mut restored), state(&mut original));
+
+        step(&mut original, 500);
+        step(&mut restored, 500);
+        assert_eq!(state(&mut restored), state(&mut original));
+    }
+}
@@ -0,0 +1,62 @@
+//! Solitons of the sine-Gordon model
+
+use bevy::prelude::*;
+
+use crate::{
+    poles::Pole,
+    settings::{HardSettings, SoftSettings},
+    wave::{Angle, AngularVelocity},
+};
+
+/// Signal for replacing the chain state with a kink in the middle of the chain
+pub struct SpawnKink {
+    /// Velocity as a fraction of the wave speed
+    pub velocity: f32,
+}
+
+/// Width of a static kink
+pub fn kink_width(settings: &SoftSettings) -> f32 {
+    (settings.stiffness / settings.gravity).sqrt()
+}
+
+/// Angle and angular velocity of a full-turn kink at distance `x` from its center
+/// `velocity` is a fraction of the wave speed, the kink is Lorentz-contracted accordingly
+pub fn kink(x: f32, width: f32, velocity: f32, speed: f32) -> (f32, f32) {
+    let gamma = 1.0 / (1.0 - velocity * velocity).sqrt();
+    let xi = gamma * x / width;
+    let angle = 4.0 * xi.exp().atan();
+    let angular_velocity = -2.0 * gamma * velocity * speed / width / xi.cosh();
+    (angle, angular_velocity)
+}
+
+fn spawn_kink(
+    mut events: EventReader<SpawnKink>,
+    mut query: Query<(&Pole, &mut Angle, &mut AngularVelocity)>,
+    soft_settings: Res<SoftSettings>,
+    hard_settings: Res<HardSettings>,
+) {
+    for event in events.iter() {
+        let width = kink_width(&soft_settings);
+        // Without stiffness the kink collapses into a discontinuity
+        if width <= 0.0 {
+            continue;
+        }
+        let speed = (soft_settings.stiffness / soft_settings.moment_of_inertia).sqrt();
+        let center = (hard_settings.amount as f32 - 1.) / 2.;
+        for (pole, mut angle, mut angular_velocity) in query.iter_mut() {
+            let x = (pole.index as f32 - center) * hard_settings.distance;
+            let (a, v) = kink(x, width, event.velocity, speed);
+            angle.0 = a;
+            angular_velocity.0 = v;
+        }
+    }
+}
+
+pub struct SolitonPlugin;
+
+impl Plugin for SolitonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnKink>()
+            .add_system(spawn_kink.before("physics"));
+    }
+}
@@ -0,0 +1,91 @@
+//! Supporting strings of the 2-string model
+//! Purely visual, the physics is in the restoring torque
+
+use bevy::prelude::*;
+
+use crate::{
+    poles::{pole_side, Pole},
+    settings::{HardSettings, Model, SoftSettings},
+    wave::Angle,
+};
+
+const THICKNESS: f32 = 0.03;
+
+/// Piece of a string between pole `index` and the pole above
+#[derive(Component)]
+pub struct StringSegment {
+    pub index: usize,
+    /// Position of the attachment point along the pole
+    pub offset: f32,
+}
+
+/// Respawns strings whenever new poles appear
+fn spawn(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    added: Query<(), Added<Pole>>,
+    segments: Query<Entity, With<StringSegment>>,
+    settings: Res<HardSettings>,
+) {
+    if added.is_empty() {
+        return;
+    }
+    for e in segments.iter() {
+        commands.entity(e).despawn();
+    }
+
+    let half_length = settings.length * pole_side(settings.amount) / 2.;
+    let mesh_handle = meshes.add(shape::Box::new(THICKNESS, 1., THICKNESS).into());
+    let material_handle = materials.add(Color::rgb_u8(0x40, 0x40, 0x40).into());
+
+    for index in 0..settings.amount.saturating_sub(1) as usize {
+        for offset in [-half_length, half_length] {
+            commands
+                .spawn_bundle(PbrBundle {
+                    mesh: mesh_handle.clone(),
+                    material: material_handle.clone(),
+                    ..Default::default()
+                })
+                .insert(StringSegment { index, offset });
+        }
+    }
+}
+
+/// Stretches string segments between the ends of neighbouring poles
+fn update(
+    poles: Query<(&Pole, &Transform, &Angle), Without<StringSegment>>,
+    mut segments: Query<(&StringSegment, &mut Transform, &mut Visibility)>,
+    settings: Res<SoftSettings>,
+) {
+    let mut ends = vec![(Vec3::ZERO, 0.0); poles.iter().count()];
+    for (pole, transform, angle) in poles.iter() {
+        ends[pole.index] = (transform.translation, angle.0);
+    }
+    let attachment = |(translation, angle): (Vec3, f32), offset: f32| {
+        translation + Quat::from_rotation_y(angle) * Vec3::new(offset, 0., 0.)
+    };
+
+    let visible = settings.model == Model::TwoString;
+    for (segment, mut transform, mut visibility) in segments.iter_mut() {
+        visibility.is_visible = visible;
+        let (below, above) = match (ends.get(segment.index), ends.get(segment.index + 1)) {
+            (Some(&below), Some(&above)) => (below, above),
+            _ => continue,
+        };
+        let start = attachment(below, segment.offset);
+        let end = attachment(above, segment.offset);
+        let direction = end - start;
+        transform.translation = (start + end) / 2.;
+        transform.rotation = Quat::from_rotation_arc(Vec3::Y, direction.normalize());
+        transform.scale = Vec3::new(1., direction.length(), 1.);
+    }
+}
+
+pub struct StringPlugin;
+
+impl Plugin for StringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn).add_system(update.after("physics"));
+    }
+}
@@ -1,9 +1,35 @@
 //! UI related stuff
 
 use bevy::{ecs::schedule::ShouldRun, prelude::*};
-use bevy_egui::{egui, EguiContext, EguiPlugin};
+use bevy_egui::{
+    egui::{
+        self,
+        plot::{Bar, BarChart, Legend, Line, Plot, VLine, Value, Values},
+    },
+    EguiContext, EguiPlugin,
+};
 
-use crate::settings::{HardReset, HardSettings, SoftSettings};
+use crate::{
+    analysis::Spectrum,
+    chain::{Energy, TorsionChain},
+    drive::Drive,
+    energy::EnergyHistory,
+    history::History,
+    medium::MAX_DISORDER,
+    modes::{self, Anchors},
+    poles::Pole,
+    presets::{BuiltIn, Preset},
+    recorder::Recorder,
+    scaled_time::ScaledTime,
+    settings::{
+        Boundary, DampingModel, HardReset, HardSettings, InitialState, Integrator, Medium, Model,
+        SoftSettings,
+    },
+    snapshot::{SnapshotEvent, SnapshotStatus},
+    soliton::{kink_width, SpawnKink},
+    waterfall::Waterfall,
+    wave::{Angle, AngularVelocity},
+};
 
 pub struct UIPlugin;
 
@@ -11,150 +37,1084 @@ impl Plugin for UIPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.add_plugin(EguiPlugin)
             .init_resource::<HelpMessage>()
+            .init_resource::<OpenWindows>()
             .add_system(settings_ui.with_run_criteria(cursor_unlocked))
+            .add_system(windows_ui.with_run_criteria(cursor_unlocked))
+            .add_system(playback_ui.with_run_criteria(cursor_unlocked))
+            .add_system(recorder_ui.with_run_criteria(cursor_unlocked))
+            .add_system(energy_ui.with_run_criteria(cursor_unlocked))
+            .add_system(shape_ui.with_run_criteria(cursor_unlocked))
+            .add_system(waterfall_ui.with_run_criteria(cursor_unlocked))
+            .add_system(spectrum_ui.with_run_criteria(cursor_unlocked))
+            .add_system(resonance_ui.with_run_criteria(cursor_unlocked))
+            .add_system(playback_keys)
             .add_system(toggle_help)
             .add_system(help_ui);
     }
 }
 
+/// Which of the optional windows are shown
+#[derive(Default)]
+struct OpenWindows {
+    energy: bool,
+    shape: bool,
+    waterfall: bool,
+    spectrum: bool,
+    resonances: bool,
+}
+
+/// Toggles of the optional windows, all closed at first
+fn windows_ui(mut open: ResMut<OpenWindows>, mut egui_context: ResMut<EguiContext>) {
+    egui::Window::new("Windows")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.checkbox(&mut open.energy, "Energy");
+            ui.checkbox(&mut open.shape, "Wave shape");
+            ui.checkbox(&mut open.waterfall, "Space-time diagram");
+            ui.checkbox(&mut open.spectrum, "Spectrum");
+            ui.checkbox(&mut open.resonances, "Resonances");
+        });
+}
+
+/// State of the settings window kept between frames
+struct SettingsState {
+    kink_velocity: f32,
+    preset_path: String,
+    /// Error of the last preset operation
+    preset_status: Option<String>,
+}
+
+impl Default for SettingsState {
+    fn default() -> Self {
+        Self {
+            kink_velocity: 0.0,
+            preset_path: "preset.ron".to_string(),
+            preset_status: None,
+        }
+    }
+}
+
 fn settings_ui(
     mut soft_settings: ResMut<SoftSettings>,
     mut hard_settings: ResMut<HardSettings>,
     mut hard_reset: ResMut<HardReset>,
+    scaled_time: Res<ScaledTime>,
+    mut spawn_kink: EventWriter<SpawnKink>,
+    mut state: Local<SettingsState>,
     mut egui_context: ResMut<EguiContext>,
 ) {
     egui::Window::new("Settings")
         .default_pos([10.0, 10.0])
         .resizable(false)
         .show(egui_context.ctx_mut(), |ui| {
-            ui.heading("General");
-            ui.add(
-                egui::Slider::new(&mut soft_settings.time_scale, 0.0..=1.0)
-                    .clamp_to_range(true)
-                    .text("Time scale"),
-            );
+            // Leaves room for the title bar, so the bottom of the window stays on screen
+            let max_height = ui.ctx().available_rect().height() - 80.0;
+            egui::ScrollArea::vertical()
+                .max_height(max_height)
+                .show(ui, |ui| {
+                    ui.heading("Presets");
+                    egui::ComboBox::from_label("Built-in")
+                        .selected_text("Choose...")
+                        .show_ui(ui, |ui| {
+                            for built_in in BuiltIn::ALL {
+                                if ui.selectable_label(false, built_in.name()).clicked() {
+                                    let preset = built_in.preset();
+                                    *soft_settings = preset.soft;
+                                    *hard_settings = preset.hard;
+                                    hard_reset.0 = true;
+                                }
+                            }
+                        });
+                    ui.horizontal(|ui| {
+                        ui.text_edit_singleline(&mut state.preset_path);
+                        if ui.button("Save preset").clicked() {
+                            let preset = Preset {
+                                soft: soft_settings.clone(),
+                                hard: hard_settings.clone(),
+                            };
+                            state.preset_status = preset.save(&state.preset_path).err();
+                        }
+                        if ui.button("Load preset").clicked() {
+                            match Preset::load(&state.preset_path) {
+                                Ok(preset) => {
+                                    *soft_settings = preset.soft;
+                                    *hard_settings = preset.hard;
+                                    hard_reset.0 = true;
+                                    state.preset_status = None;
+                                }
+                                Err(error) => state.preset_status = Some(error),
+                            }
+                        }
+                    });
+                    if let Some(error) = &state.preset_status {
+                        ui.colored_label(egui::Color32::RED, error);
+                    }
 
-            ui.add(
-                egui::Slider::new(&mut soft_settings.stiffness, 0.0..=1.0)
-                    .clamp_to_range(false)
-                    .prefix("k = ")
-                    .suffix(" N / m")
-                    .text("Stiffness"),
-            );
-            if soft_settings.stiffness < 0.0 {
-                soft_settings.stiffness = 0.0;
-            }
+                    ui.heading("General");
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.time_scale, 0.0..=100.0)
+                            .logarithmic(true)
+                            .smallest_positive(0.01)
+                            .clamp_to_range(true)
+                            .suffix("×")
+                            .text("Time scale"),
+                    );
 
-            ui.add(
-                egui::Slider::new(&mut soft_settings.moment_of_inertia, 0.01..=1.0)
-                    .clamp_to_range(false)
-                    .prefix("I = ")
-                    .suffix(" kg * m^2")
-                    .text("Moment of inertia"),
-            );
-            if soft_settings.moment_of_inertia < 0.01 {
-                soft_settings.moment_of_inertia = 0.01;
-            }
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.timestep, 0.0005..=0.01)
+                            .logarithmic(true)
+                            .clamp_to_range(true)
+                            .prefix("dt = ")
+                            .suffix(" s")
+                            .text("Time step"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.max_substeps, 1..=64)
+                            .clamp_to_range(false)
+                            .text("Max steps per frame"),
+                    );
+                    if soft_settings.max_substeps < 1 {
+                        soft_settings.max_substeps = 1;
+                    }
+                    if scaled_time.falling_behind {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            "Simulation is falling behind real time",
+                        );
+                    }
 
-            let mut speed = (soft_settings.stiffness / soft_settings.moment_of_inertia).sqrt();
-            ui.horizontal(|ui| {
-                ui.add(
-                    egui::DragValue::new(&mut speed)
-                        .prefix("v = ")
-                        .suffix(" m / s"),
-                );
-                ui.label("Wave speed (derived)");
-            });
+                    egui::ComboBox::from_label("Integrator")
+                        .selected_text(soft_settings.integrator.name())
+                        .show_ui(ui, |ui| {
+                            for integrator in Integrator::ALL {
+                                ui.selectable_value(
+                                    &mut soft_settings.integrator,
+                                    integrator,
+                                    integrator.name(),
+                                );
+                            }
+                        });
 
-            ui.add(
-                egui::Slider::new(&mut soft_settings.damping, -1.0..=0.0)
-                    .clamp_to_range(false)
-                    .prefix("α = ")
-                    .suffix(" N * s")
-                    .text("Damping"),
-            );
-            if soft_settings.damping > 0.0 {
-                soft_settings.damping = 0.0;
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.stiffness, 0.0..=1.0)
+                            .clamp_to_range(false)
+                            .prefix("k = ")
+                            .suffix(" N * m^2")
+                            .text("Torsional rigidity"),
+                    );
+                    if soft_settings.stiffness < 0.0 {
+                        soft_settings.stiffness = 0.0;
+                    }
+
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.moment_of_inertia, 0.01..=1.0)
+                            .clamp_to_range(false)
+                            .prefix("I = ")
+                            .suffix(" kg * m")
+                            .text("Moment of inertia per length"),
+                    );
+                    if soft_settings.moment_of_inertia < 0.01 {
+                        soft_settings.moment_of_inertia = 0.01;
+                    }
+
+                    let mut speed =
+                        (soft_settings.stiffness / soft_settings.moment_of_inertia).sqrt();
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::DragValue::new(&mut speed)
+                                .prefix("v = ")
+                                .suffix(" m / s"),
+                        );
+                        ui.label("Wave speed (derived)");
+                    });
+
+                    egui::ComboBox::from_label("Damping model")
+                        .selected_text(soft_settings.damping_model.name())
+                        .show_ui(ui, |ui| {
+                            for model in DampingModel::ALL {
+                                ui.selectable_value(
+                                    &mut soft_settings.damping_model,
+                                    model,
+                                    model.name(),
+                                );
+                            }
+                        });
+                    let unit = soft_settings.damping_model.unit();
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.damping, -1.0..=0.0)
+                            .clamp_to_range(false)
+                            .prefix("α = ")
+                            .suffix(unit)
+                            .text("Damping"),
+                    );
+                    if soft_settings.damping > 0.0 {
+                        soft_settings.damping = 0.0;
+                    }
+
+                    ui.separator();
+                    ui.heading("Medium");
+                    egui::ComboBox::from_label("Medium")
+                        .selected_text(soft_settings.medium.name())
+                        .show_ui(ui, |ui| {
+                            for medium in Medium::ALL {
+                                ui.selectable_value(
+                                    &mut soft_settings.medium,
+                                    medium,
+                                    medium.name(),
+                                );
+                            }
+                        });
+                    match soft_settings.medium {
+                        Medium::Uniform => {}
+                        Medium::Step | Medium::Gradient => {
+                            ui.add(
+                                egui::Slider::new(&mut soft_settings.inertia_ratio, 0.1..=10.0)
+                                    .logarithmic(true)
+                                    .clamp_to_range(false)
+                                    .text("Inertia ratio (top / bottom)"),
+                            );
+                            if soft_settings.inertia_ratio < 0.01 {
+                                soft_settings.inertia_ratio = 0.01;
+                            }
+                            ui.add(
+                                egui::Slider::new(&mut soft_settings.stiffness_ratio, 0.1..=10.0)
+                                    .logarithmic(true)
+                                    .clamp_to_range(false)
+                                    .text("Stiffness ratio (top / bottom)"),
+                            );
+                            if soft_settings.stiffness_ratio < 0.01 {
+                                soft_settings.stiffness_ratio = 0.01;
+                            }
+                        }
+                        Medium::Disorder => {
+                            ui.add(
+                                egui::Slider::new(&mut soft_settings.disorder, 0.0..=MAX_DISORDER)
+                                    .clamp_to_range(true)
+                                    .text("Disorder strength"),
+                            );
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut soft_settings.disorder_seed));
+                                ui.label("Seed");
+                            });
+                        }
+                    }
+
+                    ui.separator();
+                    ui.heading("Model");
+                    egui::ComboBox::from_label("Model")
+                        .selected_text(soft_settings.model.name())
+                        .show_ui(ui, |ui| {
+                            for model in Model::ALL {
+                                ui.selectable_value(&mut soft_settings.model, model, model.name());
+                            }
+                        });
+                    match soft_settings.model {
+                        Model::SingleString => {}
+                        Model::TwoString => {
+                            ui.add(
+                                egui::Slider::new(&mut soft_settings.string_torsion, 0.0..=1.0)
+                                    .clamp_to_range(false)
+                                    .prefix("κ = ")
+                                    .suffix(" N")
+                                    .text("String torsion per length"),
+                            );
+                            if soft_settings.string_torsion < 0.0 {
+                                soft_settings.string_torsion = 0.0;
+                            }
+
+                            let mut cutoff = (soft_settings.string_torsion
+                                / soft_settings.moment_of_inertia)
+                                .sqrt()
+                                / std::f32::consts::TAU;
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut cutoff)
+                                        .prefix("f = ")
+                                        .suffix(" 1 / s"),
+                                );
+                                ui.label("Cutoff frequency (derived)");
+                            });
+                        }
+                        Model::SineGordon => {
+                            ui.add(
+                                egui::Slider::new(&mut soft_settings.gravity, 0.01..=1.0)
+                                    .clamp_to_range(false)
+                                    .prefix("g = ")
+                                    .suffix(" N")
+                                    .text("Gravity per length"),
+                            );
+                            if soft_settings.gravity < 0.01 {
+                                soft_settings.gravity = 0.01;
+                            }
+
+                            let mut width = kink_width(&soft_settings);
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut width).prefix("λ = ").suffix(" m"),
+                                );
+                                ui.label("Kink width (derived)");
+                            });
+
+                            ui.add(
+                                egui::Slider::new(&mut state.kink_velocity, -0.95..=0.95)
+                                    .clamp_to_range(true)
+                                    .prefix("v = ")
+                                    .suffix(" c")
+                                    .text("Kink velocity"),
+                            );
+                            if ui.button("Spawn kink").clicked() {
+                                spawn_kink.send(SpawnKink {
+                                    velocity: state.kink_velocity,
+                                });
+                            }
+                        }
+                    }
+
+                    ui.separator();
+                    ui.heading("Boundaries");
+                    boundary_ui(ui, "Top", &mut soft_settings.top_boundary);
+                    boundary_ui(ui, "Bottom", &mut soft_settings.bottom_boundary);
+
+                    ui.separator();
+                    ui.heading("Sponge layers");
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.top_sponge, 0..=32)
+                            .clamp_to_range(false)
+                            .text("Top thickness (poles)"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.bottom_sponge, 0..=32)
+                            .clamp_to_range(false)
+                            .text("Bottom thickness (poles)"),
+                    );
+                    ui.add(
+                        egui::Slider::new(&mut soft_settings.sponge_damping, 0.0..=2.0)
+                            .clamp_to_range(false)
+                            .prefix("σ = ")
+                            .suffix(" N * s")
+                            .text("Sponge damping per length"),
+                    );
+                    if soft_settings.sponge_damping < 0.0 {
+                        soft_settings.sponge_damping = 0.0;
+                    }
+
+                    ui.separator();
+                    ui.heading("Top agitation");
+                    let driven = soft_settings.top_boundary == Boundary::Driven;
+                    drive_ui(ui, "top", &mut soft_settings.top_drive, driven);
+
+                    ui.separator();
+                    ui.heading("Bottom agitation");
+                    let driven = soft_settings.bottom_boundary == Boundary::Driven;
+                    drive_ui(ui, "bottom", &mut soft_settings.bottom_drive, driven);
+
+                    ui.separator();
+                    ui.heading("Initial state (requiring reset)");
+                    egui::ComboBox::from_label("Initial state")
+                        .selected_text(hard_settings.initial_state.name())
+                        .show_ui(ui, |ui| {
+                            for state in InitialState::ALL {
+                                ui.selectable_value(
+                                    &mut hard_settings.initial_state,
+                                    state,
+                                    state.name(),
+                                );
+                            }
+                        });
+                    if hard_settings.initial_state != InitialState::Rest {
+                        ui.add(
+                            egui::Slider::new(&mut hard_settings.initial_amplitude, -3.0..=3.0)
+                                .clamp_to_range(false)
+                                .prefix("θ = ")
+                                .suffix(" rad")
+                                .text("Amplitude"),
+                        );
+                    }
+                    match hard_settings.initial_state {
+                        InitialState::Rest => {}
+                        InitialState::Pulse | InitialState::Packet | InitialState::Twist => {
+                            ui.add(
+                                egui::Slider::new(&mut hard_settings.initial_width, 0.05..=5.0)
+                                    .clamp_to_range(false)
+                                    .prefix("σ = ")
+                                    .suffix(" m")
+                                    .text("Width"),
+                            );
+                            if hard_settings.initial_width < 0.01 {
+                                hard_settings.initial_width = 0.01;
+                            }
+                            ui.add(
+                                egui::Slider::new(&mut hard_settings.initial_position, 0.0..=1.0)
+                                    .clamp_to_range(true)
+                                    .text("Position (bottom to top)"),
+                            );
+                            if hard_settings.initial_state == InitialState::Packet {
+                                ui.add(
+                                    egui::Slider::new(&mut hard_settings.initial_mode, 1..=16)
+                                        .clamp_to_range(false)
+                                        .text("Wavelengths per chain"),
+                                );
+                            }
+                        }
+                        InitialState::StandingWave => {
+                            ui.add(
+                                egui::Slider::new(&mut hard_settings.initial_mode, 1..=16)
+                                    .clamp_to_range(false)
+                                    .prefix("n = ")
+                                    .text("Mode"),
+                            );
+                        }
+                        InitialState::Noise => {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut hard_settings.initial_seed));
+                                ui.label("Seed");
+                            });
+                        }
+                    }
+                    if hard_settings.initial_mode < 1 {
+                        hard_settings.initial_mode = 1;
+                    }
+
+                    ui.separator();
+                    ui.heading("Requiring reset");
+                    ui.add(
+                        egui::Slider::new(&mut hard_settings.amount, 1..=64)
+                            .clamp_to_range(false)
+                            .text("Amount of poles"),
+                    );
+                    if hard_settings.amount < 1 {
+                        hard_settings.amount = 1;
+                    }
+                    ui.add(
+                        egui::Slider::new(&mut hard_settings.length, 2.0..=16.0)
+                            .clamp_to_range(false)
+                            .text("Length of poles"),
+                    );
+                    if hard_settings.length < 2.0 {
+                        hard_settings.length = 2.0;
+                    }
+
+                    if ui.button("Reset simulation").clicked() {
+                        hard_reset.0 = true;
+                    }
+                });
+        });
+}
+
+/// Boundary condition selection for one end
+fn boundary_ui(ui: &mut egui::Ui, label: &str, boundary: &mut Boundary) {
+    egui::ComboBox::from_label(label)
+        .selected_text(boundary.name())
+        .show_ui(ui, |ui| {
+            for option in Boundary::ALL {
+                ui.selectable_value(boundary, option, option.name());
             }
+        });
+}
 
-            ui.separator();
-            ui.heading("Anchors");
-            ui.checkbox(&mut soft_settings.anchor_top, "Top");
-            ui.checkbox(&mut soft_settings.anchor_bottom, "Bottom");
+/// Eigenfrequencies of the chain, with buttons driving an end at them
+fn resonance_ui(
+    query: Query<(), With<Pole>>,
+    mut soft_settings: ResMut<SoftSettings>,
+    mut open: ResMut<OpenWindows>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    egui::Window::new("Resonances")
+        .default_pos([420.0, 300.0])
+        .open(&mut open.resonances)
+        .show(egui_context.ctx_mut(), |ui| {
+            if soft_settings.medium != Medium::Uniform {
+                ui.label("Frequencies of the uniform medium, the actual ones differ");
+            }
+            // The amount in settings only applies after a reset
+            let amount = query.iter().count() as u32;
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("resonances").show(ui, |ui| {
+                        for n in 1..=amount {
+                            let omega = modes::angular_frequency(n, amount, &soft_settings);
+                            let frequency = omega / std::f32::consts::TAU;
+                            ui.label(format!("Mode {}", n));
+                            ui.label(format!("f = {:.4} 1 / s", frequency));
+                            if ui.button("Drive top").clicked() {
+                                soft_settings.top_drive.tune(frequency);
+                            }
+                            if ui.button("Drive bottom").clicked() {
+                                soft_settings.bottom_drive.tune(frequency);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+        });
+}
 
-            ui.separator();
-            ui.heading("Top agitation");
-            ui.add(
-                egui::Slider::new(&mut soft_settings.top_frequency, -0.5..=0.5)
-                    .clamp_to_range(false)
-                    .prefix("f = ")
-                    .suffix(" 1 / s")
-                    .text("Frequency"),
-            );
+/// Waveform selection and its parameters
+/// `driven` ends are driven by the angle of their anchor instead of torque
+fn drive_ui(ui: &mut egui::Ui, id: &str, drive: &mut Drive, driven: bool) {
+    egui::ComboBox::from_id_source(id)
+        .selected_text(drive.name())
+        .show_ui(ui, |ui| {
+            for preset in Drive::presets(drive.amplitude()) {
+                if ui
+                    .selectable_label(drive.same_kind(&preset), preset.name())
+                    .clicked()
+                    && !drive.same_kind(&preset)
+                {
+                    *drive = preset;
+                }
+            }
+        });
+
+    let frequency = |frequency| {
+        egui::Slider::new(frequency, -0.5..=0.5)
+            .clamp_to_range(false)
+            .prefix("f = ")
+            .suffix(" 1 / s")
+    };
+    match drive {
+        Drive::Sine {
+            frequency: f,
+            phase,
+            ..
+        }
+        | Drive::Square {
+            frequency: f,
+            phase,
+            ..
+        }
+        | Drive::Triangle {
+            frequency: f,
+            phase,
+            ..
+        }
+        | Drive::Sawtooth {
+            frequency: f,
+            phase,
+            ..
+        } => {
+            ui.add(frequency(f).text("Frequency"));
             ui.add(
-                egui::Slider::new(&mut soft_settings.top_phase, -0.5..=0.5)
+                egui::Slider::new(phase, -0.5..=0.5)
                     .clamp_to_range(false)
                     .prefix("φ = ")
                     .suffix(" rad")
                     .text("Phase"),
             );
+        }
+        Drive::Pulse { delay, width, .. } => {
             ui.add(
-                egui::Slider::new(&mut soft_settings.top_force, -1.0..=1.0)
+                egui::Slider::new(delay, 0.0..=10.0)
                     .clamp_to_range(false)
-                    .prefix("M = ")
-                    .suffix(" N * m")
-                    .text("Torque"),
+                    .prefix("t = ")
+                    .suffix(" s")
+                    .text("Peak time"),
             );
-
-            ui.separator();
-            ui.heading("Bottom agitation");
             ui.add(
-                egui::Slider::new(&mut soft_settings.bottom_frequency, -0.5..=0.5)
+                egui::Slider::new(width, 0.01..=1.0)
                     .clamp_to_range(false)
-                    .prefix("f = ")
-                    .suffix(" 1 / s")
-                    .text("Frequency"),
+                    .prefix("σ = ")
+                    .suffix(" s")
+                    .text("Width"),
             );
+            if *width < 0.01 {
+                *width = 0.01;
+            }
+        }
+        Drive::Chirp {
+            start_frequency,
+            end_frequency,
+            duration,
+            ..
+        } => {
+            ui.add(frequency(start_frequency).text("Start frequency"));
+            ui.add(frequency(end_frequency).text("End frequency"));
             ui.add(
-                egui::Slider::new(&mut soft_settings.bottom_phase, -0.5..=0.5)
+                egui::Slider::new(duration, 1.0..=120.0)
                     .clamp_to_range(false)
-                    .prefix("φ = ")
-                    .suffix(" rad")
-                    .text("Phase"),
+                    .prefix("T = ")
+                    .suffix(" s")
+                    .text("Sweep duration"),
             );
+            if *duration < 0.1 {
+                *duration = 0.1;
+            }
+        }
+        Drive::Noise {
+            bandwidth, seed, ..
+        } => {
             ui.add(
-                egui::Slider::new(&mut soft_settings.bottom_force, -1.0..=1.0)
-                    .clamp_to_range(false)
-                    .prefix("M = ")
-                    .suffix(" N * m")
-                    .text("Torque"),
+                egui::Slider::new(bandwidth, 0.1..=100.0)
+                    .logarithmic(true)
+                    .clamp_to_range(true)
+                    .suffix(" 1 / s")
+                    .text("Bandwidth"),
             );
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(seed));
+                ui.label("Seed");
+            });
+        }
+    }
 
-            ui.separator();
-            ui.heading("Requiring reset");
-            ui.add(
-                egui::Slider::new(&mut hard_settings.amount, 1..=64)
-                    .clamp_to_range(false)
-                    .text("Amount of poles"),
-            );
-            if hard_settings.amount < 1 {
-                hard_settings.amount = 1;
+    match driven {
+        true => ui.add(
+            egui::Slider::new(drive.amplitude_mut(), -1.0..=1.0)
+                .clamp_to_range(false)
+                .prefix("θ = ")
+                .suffix(" rad")
+                .text("Angle"),
+        ),
+        false => ui.add(
+            egui::Slider::new(drive.amplitude_mut(), -1.0..=1.0)
+                .clamp_to_range(false)
+                .prefix("M = ")
+                .suffix(" N * m")
+                .text("Torque"),
+        ),
+    };
+}
+
+/// State of the playback window kept between frames
+struct PlaybackState {
+    snapshot_path: String,
+}
+
+impl Default for PlaybackState {
+    fn default() -> Self {
+        Self {
+            snapshot_path: "snapshot.ron".to_string(),
+        }
+    }
+}
+
+fn playback_ui(
+    mut scaled_time: ResMut<ScaledTime>,
+    mut history: ResMut<History>,
+    mut settings: ResMut<SoftSettings>,
+    mut state: Local<PlaybackState>,
+    mut snapshot_events: EventWriter<SnapshotEvent>,
+    snapshot_status: Res<SnapshotStatus>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    egui::Window::new("Playback")
+        .anchor(egui::Align2::LEFT_BOTTOM, [10.0, -10.0])
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.label(format!("t = {:.3} s", scaled_time.total));
+            ui.horizontal(|ui| {
+                let label = if scaled_time.paused {
+                    "Resume"
+                } else {
+                    "Pause"
+                };
+                if ui.button(label).clicked() {
+                    scaled_time.paused = !scaled_time.paused;
+                }
+                if ui.button("Step").clicked() {
+                    scaled_time.step(1);
+                }
+                if ui.button(format!("Step {}", settings.step_ticks)).clicked() {
+                    let ticks = settings.step_ticks;
+                    scaled_time.step(ticks);
+                }
+                ui.add(egui::DragValue::new(&mut settings.step_ticks).clamp_range(1..=10000));
+            });
+
+            if scaled_time.paused && !history.frames.is_empty() {
+                let last = history.frames.len() - 1;
+                let mut cursor = history.cursor.unwrap_or(last);
+                let response = ui.add(
+                    egui::Slider::new(&mut cursor, 0..=last)
+                        .show_value(false)
+                        .text("Rewind"),
+                );
+                if response.changed() {
+                    history.cursor = Some(cursor);
+                }
             }
+
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut state.snapshot_path);
+                if ui.button("Save snapshot").clicked() {
+                    snapshot_events.send(SnapshotEvent::Save((&state.snapshot_path).into()));
+                }
+                if ui.button("Load snapshot").clicked() {
+                    snapshot_events.send(SnapshotEvent::Load((&state.snapshot_path).into()));
+                }
+            });
+            if let Some(error) = &snapshot_status.0 {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+        });
+}
+
+/// State of the recorder window kept between frames
+struct RecorderState {
+    path: String,
+    /// Result of the last export
+    status: Option<Result<String, String>>,
+}
+
+impl Default for RecorderState {
+    fn default() -> Self {
+        Self {
+            path: "recording.csv".to_string(),
+            status: None,
+        }
+    }
+}
+
+fn recorder_ui(
+    mut recorder: ResMut<Recorder>,
+    mut state: Local<RecorderState>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    egui::Window::new("Recorder")
+        .default_pos([420.0, 10.0])
+        .resizable(false)
+        .show(egui_context.ctx_mut(), |ui| {
             ui.add(
-                egui::Slider::new(&mut hard_settings.length, 2.0..=16.0)
+                egui::Slider::new(&mut recorder.interval, 0.0..=1.0)
                     .clamp_to_range(false)
-                    .text("Length of poles"),
+                    .prefix("Δt = ")
+                    .suffix(" s")
+                    .text("Sampling interval"),
             );
-            if hard_settings.length < 2.0 {
-                hard_settings.length = 2.0;
+            if recorder.interval < 0.0 {
+                recorder.interval = 0.0;
             }
+            ui.horizontal(|ui| {
+                if recorder.recording {
+                    if ui.button("Stop recording").clicked() {
+                        recorder.stop();
+                    }
+                } else if ui.button("Start recording").clicked() {
+                    recorder.start();
+                }
+                ui.label(format!("{} samples", recorder.len()));
+            });
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut state.path);
+                let export = egui::Button::new("Export CSV");
+                if ui.add_enabled(!recorder.is_empty(), export).clicked() {
+                    state.status = Some(
+                        recorder
+                            .export_csv(&state.path)
+                            .map(|_| format!("Exported to {}", state.path)),
+                    );
+                }
+            });
+            match &state.status {
+                Some(Ok(message)) => {
+                    ui.label(message);
+                }
+                Some(Err(error)) => {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                None => {}
+            }
+        });
+}
+
+fn energy_ui(
+    history: Res<EnergyHistory>,
+    mut open: ResMut<OpenWindows>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    egui::Window::new("Energy")
+        .default_pos([420.0, 200.0])
+        .open(&mut open.energy)
+        .show(egui_context.ctx_mut(), |ui| {
+            let energy = history.latest();
+            egui::Grid::new("energy").show(ui, |ui| {
+                let mut row = |name: &str, value: f32, unit: &str| {
+                    ui.label(name);
+                    ui.label(format!("{:.6}{}", value, unit));
+                    ui.end_row();
+                };
+                row("Kinetic", energy.kinetic, " J");
+                row("Potential", energy.potential, " J");
+                row("Total", energy.total(), " J");
+                row("Injected power", energy.injected, " W");
+                row("Dissipated power", energy.dissipated, " W");
+            });
+
+            let line = |name: &str, quantity: fn(&Energy) -> f32| {
+                let values = history
+                    .samples
+                    .iter()
+                    .map(|(time, energy)| Value::new(*time, quantity(energy)));
+                Line::new(Values::from_values_iter(values)).name(name)
+            };
+            Plot::new("energy-plot")
+                .height(150.0)
+                .include_y(0.0)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    plot_ui.line(line("Kinetic", |e| e.kinetic));
+                    plot_ui.line(line("Potential", |e| e.potential));
+                    plot_ui.line(line("Total", Energy::total));
+                });
+            Plot::new("power-plot")
+                .height(100.0)
+                .include_y(0.0)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    plot_ui.line(line("Injected", |e| e.injected));
+                    plot_ui.line(line("Dissipated", |e| e.dissipated));
+                });
+        });
+}
+
+/// State of the wave shape window kept between frames
+#[derive(Default)]
+struct ShapeState {
+    show_velocity: bool,
+    show_steady_state: bool,
+}
 
-            if ui.button("Reset simulation").clicked() {
-                hard_reset.0 = true;
+/// Angle of every pole against its height
+fn shape_ui(
+    query: Query<(&Pole, &Angle, &AngularVelocity)>,
+    chain: Res<TorsionChain>,
+    scaled_time: Res<ScaledTime>,
+    hard_settings: Res<HardSettings>,
+    mut state: Local<ShapeState>,
+    mut open: ResMut<OpenWindows>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    egui::Window::new("Wave shape")
+        .default_pos([420.0, 500.0])
+        .open(&mut open.shape)
+        .show(egui_context.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut state.show_velocity, "Angular velocity");
+                ui.checkbox(&mut state.show_steady_state, "Steady state");
+            });
+            let height = |index: usize| (index as f32 * hard_settings.distance) as f64;
+            let mut poles = query.iter().collect::<Vec<_>>();
+            poles.sort_unstable_by_key(|(pole, ..)| pole.index);
+
+            let steady_state = match state.show_steady_state {
+                true => chain.steady_state(scaled_time.total),
+                false => None,
+            };
+            if state.show_steady_state && steady_state.is_none() {
+                ui.label("Steady state is only known for sine drives of a stable chain");
+            }
+
+            Plot::new("shape-plot")
+                .height(300.0)
+                .legend(Legend::default())
+                .show(ui, |plot_ui| {
+                    let angles = poles
+                        .iter()
+                        .map(|(pole, angle, _)| Value::new(angle.0, height(pole.index)));
+                    plot_ui.line(Line::new(Values::from_values_iter(angles)).name("Angle"));
+                    if state.show_velocity {
+                        let velocities = poles.iter().map(|(pole, _, angular_velocity)| {
+                            Value::new(angular_velocity.0, height(pole.index))
+                        });
+                        let line = Line::new(Values::from_values_iter(velocities));
+                        plot_ui.line(line.name("Angular velocity"));
+                    }
+                    if let Some(angles) = steady_state {
+                        let angles = angles
+                            .into_iter()
+                            .enumerate()
+                            .map(|(index, angle)| Value::new(angle, height(index)));
+                        let line = Line::new(Values::from_values_iter(angles));
+                        plot_ui.line(line.name("Steady state"));
+                    }
+                });
+        });
+}
+
+/// Colour of an angle in the waterfall diagram, blue for negative and red for positive
+fn diverging_color(value: f32) -> egui::Color32 {
+    let t = value.clamp(-1.0, 1.0);
+    let fade = |t: f32| (255.0 * (1.0 - t.abs())) as u8;
+    match t < 0.0 {
+        true => egui::Color32::from_rgb(fade(t), fade(t), 255),
+        false => egui::Color32::from_rgb(255, fade(t), fade(t)),
+    }
+}
+
+/// State of the waterfall window kept between frames
+#[derive(Default)]
+struct WaterfallState {
+    texture: Option<egui::TextureHandle>,
+}
+
+/// Angle of every pole over time, the newest row at the top
+fn waterfall_ui(
+    mut waterfall: ResMut<Waterfall>,
+    mut state: Local<WaterfallState>,
+    mut open: ResMut<OpenWindows>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if !open.waterfall {
+        return;
+    }
+    let ctx = egui_context.ctx_mut();
+    let width = waterfall.rows.back().map_or(0, Vec::len);
+    let height = waterfall.rows.len();
+    let texture = match width * height {
+        0 => None,
+        _ => {
+            let peak = waterfall.peak().max(f32::EPSILON);
+            let pixels = waterfall
+                .rows
+                .iter()
+                .rev()
+                .flat_map(|row| row.iter().map(|angle| diverging_color(angle / peak)))
+                .collect();
+            let image = egui::ColorImage {
+                size: [width, height],
+                pixels,
+            };
+            let texture = match &mut state.texture {
+                Some(texture) => {
+                    texture.set(image);
+                    texture
+                }
+                None => state.texture.insert(ctx.load_texture("waterfall", image)),
+            };
+            Some((texture.id(), peak))
+        }
+    };
+
+    egui::Window::new("Space-time diagram")
+        .default_pos([820.0, 10.0])
+        .open(&mut open.waterfall)
+        .show(ctx, |ui| {
+            let interval = egui::Slider::new(&mut waterfall.interval, 0.005..=0.5)
+                .logarithmic(true)
+                .clamp_to_range(true)
+                .prefix("Δt = ")
+                .suffix(" s")
+                .text("Row interval");
+            if ui.add(interval).changed() {
+                waterfall.clear();
+            }
+            ui.label(format!(
+                "Covers {:.1} s, bottom pole on the left",
+                waterfall.duration()
+            ));
+            match texture {
+                Some((id, peak)) => {
+                    ui.label(format!("Blue -{:.3} rad, red {:.3} rad", peak, peak));
+                    ui.image(id, [300.0, 300.0]);
+                }
+                None => {
+                    ui.label("Waiting for the simulation");
+                }
+            }
+        });
+}
+
+/// Amplitudes of normal modes and the frequency spectrum of a single pole
+fn spectrum_ui(
+    query: Query<(&Pole, &Angle, &AngularVelocity)>,
+    soft_settings: Res<SoftSettings>,
+    mut spectrum: ResMut<Spectrum>,
+    mut open: ResMut<OpenWindows>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    egui::Window::new("Spectrum")
+        .default_pos([820.0, 450.0])
+        .open(&mut open.spectrum)
+        .show(egui_context.ctx_mut(), |ui| {
+            let amount = query.iter().count();
+            let mut angles = vec![0.0; amount];
+            let mut velocities = vec![0.0; amount];
+            for (pole, angle, angular_velocity) in query.iter() {
+                angles[pole.index] = angle.0;
+                velocities[pole.index] = angular_velocity.0;
+            }
+            let anchors = Anchors::new(&soft_settings);
+            // The amount in settings only applies after a reset
+            let frequency = |n| modes::angular_frequency(n, amount as u32, &soft_settings);
+
+            ui.label("Normal mode amplitudes");
+            let bars = (1..=amount as u32)
+                .map(|n| {
+                    let amplitude =
+                        modes::amplitude(n, &angles, &velocities, anchors, frequency(n));
+                    Bar::new(n as f64, amplitude as f64).width(0.8)
+                })
+                .collect();
+            Plot::new("modes-plot")
+                .height(150.0)
+                .include_y(0.0)
+                .show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new(bars)));
+
+            ui.horizontal(|ui| {
+                let last = amount.saturating_sub(1);
+                let pole = egui::DragValue::new(&mut spectrum.pole).clamp_range(0..=last);
+                if ui.add(pole).changed() {
+                    spectrum.clear();
+                }
+                ui.label("Observed pole");
+            });
+            let interval = egui::Slider::new(&mut spectrum.interval, 0.005..=0.5)
+                .logarithmic(true)
+                .clamp_to_range(true)
+                .prefix("Δt = ")
+                .suffix(" s")
+                .text("Sampling interval");
+            if ui.add(interval).changed() {
+                spectrum.clear();
             }
+            ui.label(format!(
+                "Angle spectrum of the last {:.1} s, lines at eigenfrequencies",
+                spectrum.duration()
+            ));
+            let magnitudes = spectrum
+                .magnitudes()
+                .into_iter()
+                .map(|(f, magnitude)| Value::new(f, magnitude));
+            let nyquist = 0.5 / spectrum.interval;
+            Plot::new("spectrum-plot")
+                .height(150.0)
+                .include_y(0.0)
+                .show(ui, |plot_ui| {
+                    for n in 1..=amount as u32 {
+                        let f = frequency(n) / std::f32::consts::TAU;
+                        if f > nyquist {
+                            break;
+                        }
+                        plot_ui.vline(VLine::new(f).color(egui::Color32::GRAY));
+                    }
+                    plot_ui.line(Line::new(Values::from_values_iter(magnitudes)));
+                });
         });
 }
 
+/// Pausing with `P`, stepping once with `.` and stepping multiple times with `,`
+fn playback_keys(
+    keys: Res<Input<KeyCode>>,
+    mut scaled_time: ResMut<ScaledTime>,
+    settings: Res<SoftSettings>,
+    mut egui_context: ResMut<EguiContext>,
+) {
+    if egui_context.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+    if keys.just_pressed(KeyCode::P) {
+        scaled_time.paused = !scaled_time.paused;
+    }
+    if keys.just_pressed(KeyCode::Period) {
+        scaled_time.step(1);
+    }
+    if keys.just_pressed(KeyCode::Comma) {
+        scaled_time.step(settings.step_ticks);
+    }
+}
+
 struct HelpMessage(bool);
 
 impl Default for HelpMessage {
@@ -187,6 +1147,16 @@ fn help_ui(mut egui_context: ResMut<EguiContext>, help_message: ResMut<HelpMessa
                         .color(color)
                         .background_color(bg_color),
                 );
+                ui.label(
+                    egui::RichText::new("Pause with `P`, step once with `.` and step multiple times with `,`.")
+                        .color(color)
+                        .background_color(bg_color),
+                );
+                ui.label(
+                    egui::RichText::new("Grab a pole with the left mouse button and drag sideways to twist it.")
+                        .color(color)
+                        .background_color(bg_color),
+                );
                 ui.label(
                     egui::RichText::new("Adjust simulation settings with graphical interface.")
                         .color(color)
@@ -0,0 +1,81 @@
+//! Space-time (waterfall) diagram of the chain
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::chain::TorsionChain;
+
+/// Rows kept in the diagram
+const CAPACITY: usize = 256;
+
+/// Angles of the poles sampled at regular intervals, the newest at the back
+pub struct Waterfall {
+    pub rows: VecDeque<Vec<f32>>,
+    /// Time between rows
+    pub interval: f32,
+    /// Time of the next row
+    next_row: f64,
+}
+
+impl Default for Waterfall {
+    fn default() -> Self {
+        Self {
+            rows: VecDeque::with_capacity(CAPACITY),
+            interval: 0.05,
+            next_row: 0.0,
+        }
+    }
+}
+
+impl Waterfall {
+    /// Discards collected rows, e.g. after changing the interval
+    pub fn clear(&mut self) {
+        self.rows.clear();
+    }
+
+    /// Span of time covered by a full diagram
+    pub fn duration(&self) -> f32 {
+        self.interval * CAPACITY as f32
+    }
+
+    /// Largest magnitude of the angles in the diagram
+    pub fn peak(&self) -> f32 {
+        self.rows
+            .iter()
+            .flatten()
+            .fold(0.0, |peak, angle| peak.max(angle.abs()))
+    }
+}
+
+/// Samples the chain after each physics step
+fn sample(mut waterfall: ResMut<Waterfall>, chain: Res<TorsionChain>) {
+    // Starts over when the chain was reset, or time jumped backwards
+    let reset = waterfall
+        .rows
+        .back()
+        .is_some_and(|row| row.len() != chain.angles.len());
+    let rewound = waterfall.next_row - chain.time > waterfall.interval as f64;
+    if reset || rewound {
+        waterfall.rows.clear();
+        waterfall.next_row = chain.time;
+    }
+    if chain.time < waterfall.next_row - 1e-6 {
+        return;
+    }
+    if waterfall.rows.len() == CAPACITY {
+        waterfall.rows.pop_front();
+    }
+    waterfall.rows.push_back(chain.angles.clone());
+    let interval = waterfall.interval as f64;
+    waterfall.next_row = (waterfall.next_row + interval).max(chain.time);
+}
+
+pub struct WaterfallPlugin;
+
+impl Plugin for WaterfallPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Waterfall>()
+            .add_system(sample.with_run_criteria("physics-tick").after("step-chain"));
+    }
+}